@@ -40,7 +40,7 @@ fn main() {
     // less be its own object in the nphysics World, however if it's just a
     // PhysicsCollider the parent/child hierarchy will actually take effect and the
     // collider will be attached to the parent
-    let _child = world
+    let child = world
         .create_entity()
         .with(SimplePosition::<f32>(Isometry3::<f32>::translation(
             1.0, 1.0, 1.0,
@@ -55,6 +55,24 @@ fn main() {
         .with(PhysicsParent { entity: parent })
         .build();
 
+    // hierarchies can be arbitrarily deep; this grandchild has no PhysicsBody
+    // either, so its collider is attached to the nearest ancestor with a body,
+    // which is the parent Entity
+    let _grandchild = world
+        .create_entity()
+        .with(SimplePosition::<f32>(Isometry3::<f32>::translation(
+            1.0, 2.0, 1.0,
+        )))
+        .with(
+            PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                half_extents: Vector3::new(0.5, 0.5, 0.5),
+            })
+            .sensor(true)
+            .build(),
+        )
+        .with(PhysicsParent { entity: child })
+        .build();
+
     // execute the dispatcher
     dispatcher.dispatch(&world.res);
 }
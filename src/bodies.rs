@@ -4,7 +4,7 @@ use specs::{Component, DenseVecStorage, FlaggedStorage};
 #[cfg(feature = "physics3d")]
 use nalgebra::Matrix3;
 
-use nphysics::{math::{Force, ForceType, Point, Velocity},
+use nphysics::{math::{Force, ForceType, Isometry, Point, Velocity},
                object::{Body, BodyPart, BodyStatus, DefaultBodyHandle, RigidBody, RigidBodyDesc}};
 
 // Note: `nphysics::math::AngularInertia` could have been used in this file, but
@@ -25,6 +25,18 @@ pub struct PhysicsBody<N: RealField> {
     pub angular_inertia: N,
     pub mass: N,
     pub local_center_of_mass: Point<N>,
+    /// If set, `Pose` changes of a `BodyStatus::Kinematic` body are converted
+    /// into a velocity over the current `TimeStep` instead of teleporting the
+    /// body, so that touching dynamic bodies receive friction and push
+    /// responses.
+    pub infer_kinematic_velocity: bool,
+    /// Enables continuous collision detection for all colliders attached to
+    /// this body, so fast bodies don't tunnel through thin colliders. It can be
+    /// overridden per collider through `PhysicsCollider::ccd_enabled`.
+    pub ccd_enabled: bool,
+    /// Lets the body be affected by `GravityField`s, independent of
+    /// `gravity_enabled` which only affects the global `Gravity`.
+    pub gravity_fields_enabled: bool,
     external_forces: Force<N>,
 }
 
@@ -43,34 +55,69 @@ impl<N: RealField> PhysicsBody<N> {
     }
 
     /// For creating new rigid body from this component's values
-    pub(crate) fn to_rigid_body_desc(&self) -> RigidBodyDesc<N> {
+    pub(crate) fn to_rigid_body_desc(&self, time_scale: N) -> RigidBodyDesc<N> {
         RigidBodyDesc::new()
             .gravity_enabled(self.gravity_enabled)
             .status(self.body_status)
-            .velocity(self.velocity)
+            .velocity(self.velocity * time_scale)
             .angular_inertia(self.angular_inertia)
             .mass(self.mass)
             .local_center_of_mass(self.local_center_of_mass)
     }
 
     /// Note: applies forces by draining external force property
-    pub(crate) fn apply_to_physics_world(&mut self, rigid_body: &mut RigidBody<N>) -> &mut Self {
+    pub(crate) fn apply_to_physics_world(
+        &mut self,
+        rigid_body: &mut RigidBody<N>,
+        time_scale: N,
+    ) -> &mut Self {
         rigid_body.enable_gravity(self.gravity_enabled);
         rigid_body.set_status(self.body_status);
-        rigid_body.set_velocity(self.velocity);
+        rigid_body.set_velocity(self.velocity * time_scale);
         rigid_body.set_angular_inertia(self.angular_inertia);
         rigid_body.set_mass(self.mass);
         rigid_body.set_local_center_of_mass(self.local_center_of_mass);
-        rigid_body.apply_force(0, &self.drain_external_force(), ForceType::Force, true);
+        // accelerations scale quadratically, as both the velocity they change and
+        // the time they act over are scaled
+        let force = self.drain_external_force() * (time_scale * time_scale);
+        rigid_body.apply_force(0, &force, ForceType::Force, true);
         self
     }
 
-    pub(crate) fn update_from_physics_world(&mut self, rigid_body: &RigidBody<N>) -> &mut Self {
+    /// Moves the rigid body to the given `Pose` isometry. Kinematic bodies with
+    /// `infer_kinematic_velocity` set are given the velocity required to reach
+    /// it within `time_step`, scaled by their `time_scale` like all other
+    /// velocities; all other bodies are teleported.
+    pub(crate) fn apply_pose_to_physics_world(
+        &mut self,
+        rigid_body: &mut RigidBody<N>,
+        isometry: Isometry<N>,
+        time_step: N,
+        time_scale: N,
+    ) -> &mut Self {
+        if self.infer_kinematic_velocity
+            && self.body_status == BodyStatus::Kinematic
+            && time_step > N::zero()
+        {
+            self.velocity =
+                Velocity::between_positions(rigid_body.position(), &isometry, time_step);
+            rigid_body.set_velocity(self.velocity * time_scale);
+        } else {
+            rigid_body.set_position(isometry);
+        }
+        self
+    }
+
+    pub(crate) fn update_from_physics_world(
+        &mut self,
+        rigid_body: &RigidBody<N>,
+        time_scale: N,
+    ) -> &mut Self {
         // These two probably won't be modified but hey
         self.gravity_enabled = rigid_body.gravity_enabled();
         self.body_status = rigid_body.status();
 
-        self.velocity = *rigid_body.velocity();
+        self.velocity = *rigid_body.velocity() * (N::one() / time_scale);
 
         let local_inertia = rigid_body.local_inertia();
         self.angular_inertia = local_inertia.angular;
@@ -85,6 +132,33 @@ impl<N: RealField> PhysicsBody<N> {
     }
 }
 
+/// The `PhysicsTimeScale` `Component` slows down or speeds up the simulation
+/// of a single `PhysicsBody`, e.g. for localised slow motion. The velocity of
+/// the `RigidBody` as well as the acceleration by gravity and external forces
+/// are scaled, while its mass stays untouched so contacts with unscaled bodies
+/// remain stable.
+///
+/// `PhysicsBody::velocity` keeps holding the unscaled velocity, so removing
+/// the `Component` restores the original momentum of the body. Values less
+/// than or equal to zero are ignored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsTimeScale<N: RealField>(pub N);
+
+impl<N: RealField> Component for PhysicsTimeScale<N> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+impl<N: RealField> PhysicsTimeScale<N> {
+    /// Returns the effective time scale of a body with the given, optional
+    /// `PhysicsTimeScale`.
+    pub(crate) fn of(time_scale: Option<&Self>) -> N {
+        match time_scale {
+            Some(time_scale) if time_scale.0 > N::zero() => time_scale.0,
+            _ => N::one(),
+        }
+    }
+}
+
 /// The `PhysicsBodyBuilder` implements the builder pattern for `PhysicsBody`s
 /// and is the recommended way of instantiating and customising new
 /// `PhysicsBody` instances.
@@ -104,6 +178,7 @@ impl<N: RealField> PhysicsBody<N> {
 ///     .angular_inertia(Matrix3::from_diagonal_element(3.0))
 ///     .mass(1.3)
 ///     .local_center_of_mass(Point3::new(0.0, 0.0, 0.0))
+///     .ccd_enabled(true)
 ///     .build();
 /// ```
 pub struct PhysicsBodyBuilder<N: RealField> {
@@ -116,6 +191,9 @@ pub struct PhysicsBodyBuilder<N: RealField> {
     angular_inertia: N,
     mass: N,
     local_center_of_mass: Point<N>,
+    infer_kinematic_velocity: bool,
+    ccd_enabled: bool,
+    gravity_fields_enabled: bool,
 }
 
 impl<N: RealField> From<BodyStatus> for PhysicsBodyBuilder<N> {
@@ -132,6 +210,9 @@ impl<N: RealField> From<BodyStatus> for PhysicsBodyBuilder<N> {
             angular_inertia: N::zero(),
             mass: N::from_f32(1.2).unwrap(),
             local_center_of_mass: Point::origin(),
+            infer_kinematic_velocity: false,
+            ccd_enabled: false,
+            gravity_fields_enabled: true,
         }
     }
 }
@@ -175,6 +256,25 @@ impl<N: RealField> PhysicsBodyBuilder<N> {
         self
     }
 
+    /// Sets the `infer_kinematic_velocity` value of the `PhysicsBodyBuilder`.
+    /// This only has an effect on `BodyStatus::Kinematic` bodies.
+    pub fn infer_kinematic_velocity(mut self, infer_kinematic_velocity: bool) -> Self {
+        self.infer_kinematic_velocity = infer_kinematic_velocity;
+        self
+    }
+
+    /// Sets the `ccd_enabled` value of the `PhysicsBodyBuilder`.
+    pub fn ccd_enabled(mut self, ccd_enabled: bool) -> Self {
+        self.ccd_enabled = ccd_enabled;
+        self
+    }
+
+    /// Sets the `gravity_fields_enabled` value of the `PhysicsBodyBuilder`.
+    pub fn gravity_fields_enabled(mut self, gravity_fields_enabled: bool) -> Self {
+        self.gravity_fields_enabled = gravity_fields_enabled;
+        self
+    }
+
     /// Builds the `PhysicsBody` from the values set in the `PhysicsBodyBuilder`
     /// instance.
     pub fn build(self) -> PhysicsBody<N> {
@@ -186,6 +286,9 @@ impl<N: RealField> PhysicsBodyBuilder<N> {
             angular_inertia: self.angular_inertia,
             mass: self.mass,
             local_center_of_mass: self.local_center_of_mass,
+            infer_kinematic_velocity: self.infer_kinematic_velocity,
+            ccd_enabled: self.ccd_enabled,
+            gravity_fields_enabled: self.gravity_fields_enabled,
             external_forces: Force::zero(),
         }
     }
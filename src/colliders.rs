@@ -1,17 +1,26 @@
-use std::{f64::consts::PI, fmt, ops::Deref};
+use std::{collections::{HashMap, HashSet},
+          f64::consts::PI,
+          fmt, mem,
+          ops::{Deref, DerefMut, Range}};
 
-use specs::{Component, DenseVecStorage, Entities, Entity, FlaggedStorage};
+use specs::{world::Index, Component, DenseVecStorage, Entities, Entity, FlaggedStorage};
 
+use crate::{error::PhysicsError, layers::CollisionLayers, materials::PhysicsMaterials};
 use nalgebra::{convert, Point2, Point3, RealField, Unit};
 use ncollide::pipeline::CollisionGroups;
 use ncollide::shape::{Ball, Capsule, Compound, Cuboid, HeightField, Plane, Polyline, Segment,
                       ShapeHandle};
 use nphysics::material::MaterialHandle;
-use nphysics::math::{Isometry, Point, Vector};
-use nphysics::object::{ColliderDesc, DefaultColliderHandle, DefaultColliderSet};
+use nphysics::math::{Isometry, Point, Translation, Vector};
+use nphysics::object::{BodyStatus, Collider, ColliderDesc, DefaultBodyHandle,
+                       DefaultColliderHandle, DefaultColliderSet};
 
 #[cfg(feature = "physics3d")]
-use ncollide::shape::{ConvexHull, TriMesh, Triangle};
+use ncollide::{procedural,
+               shape::{ConvexHull, TriMesh, Triangle}};
+
+#[cfg(feature = "physics2d")]
+use ncollide::shape::ConvexPolygon;
 
 #[cfg(feature = "physics3d")]
 use nalgebra::DMatrix;
@@ -49,15 +58,44 @@ pub fn collider_handle_to_entity<N: RealField>(
     entities: &Entities,
     colliders: &DefaultColliderSet<N>,
 ) -> Option<Entity> {
-    let id = colliders.get(handle)?.user_data()?.downcast_ref()?;
-    let entity = entities.entity(*id);
+    collider_handle_to_entity_part(handle, entities, colliders).map(|(entity, _)| entity)
+}
+
+/// Converts a `DefaultColliderHandle` into the specs Entity that collider is
+/// attached to, together with the index of its definition if it was created
+/// from a `PhysicsColliders` `Component`. If the handle is invalid or the
+/// entity is no longer alive, `None` will be returned instead.
+pub fn collider_handle_to_entity_part<N: RealField>(
+    handle: DefaultColliderHandle,
+    entities: &Entities,
+    colliders: &DefaultColliderSet<N>,
+) -> Option<(Entity, Option<usize>)> {
+    let (id, part) = collider_user_data(colliders.get(handle)?)?;
+    let entity = entities.entity(id);
 
     match entities.is_alive(entity) {
-        true => Some(entity),
+        true => Some((entity, part)),
         false => None,
     }
 }
 
+/// Extracts the entity `Index` and, for colliders created from a
+/// `PhysicsColliders` `Component`, the index of the collider definition from
+/// the user data of a physics `Collider`.
+pub(crate) fn collider_user_data<N: RealField>(
+    collider: &Collider<N, DefaultBodyHandle>,
+) -> Option<(Index, Option<usize>)> {
+    let user_data = collider.user_data()?;
+
+    if let Some(id) = user_data.downcast_ref::<Index>() {
+        return Some((*id, None));
+    }
+
+    user_data
+        .downcast_ref::<(Index, usize)>()
+        .map(|(id, part)| (*id, Some(*part)))
+}
+
 /// `Shape` serves as an abstraction over nphysics `ShapeHandle`s and makes it
 /// easier to configure and define said `ShapeHandle`s for the user without
 /// having to know the underlying nphysics API.
@@ -73,13 +111,34 @@ pub enum Shape<N: RealField> {
     Compound {
         parts: Vec<(Isometry<N>, Shape<N>)>,
     },
+    /// A cone along the y axis, approximated by a convex hull with
+    /// `subdivisions` points around its base.
+    #[cfg(feature = "physics3d")]
+    Cone {
+        half_height: N,
+        radius: N,
+        subdivisions: u32,
+    },
     #[cfg(feature = "physics3d")]
     ConvexHull {
         points: Vec<Point<N>>,
     },
+    /// A solid convex polygon; the convex hull of `points` is used.
+    #[cfg(feature = "physics2d")]
+    ConvexPolygon {
+        points: Vec<Point<N>>,
+    },
     Cuboid {
         half_extents: Vector<N>,
     },
+    /// A cylinder along the y axis, approximated by a convex hull with
+    /// `subdivisions` points around each cap.
+    #[cfg(feature = "physics3d")]
+    Cylinder {
+        half_height: N,
+        radius: N,
+        subdivisions: u32,
+    },
     HeightField {
         #[cfg(feature = "physics3d")]
         heights: DMatrix<N>,
@@ -94,10 +153,21 @@ pub enum Shape<N: RealField> {
         points: Vec<Point<N>>,
         indices: Option<Vec<Point2<usize>>>,
     },
+    /// A cuboid with rounded edges and corners, approximated by a convex hull.
+    /// The `half_extents` include the `border_radius`, each rounded corner is
+    /// sampled with `subdivisions` points per circle.
+    RoundedCuboid {
+        half_extents: Vector<N>,
+        border_radius: N,
+        subdivisions: u32,
+    },
     Segment {
         a: Point<N>,
         b: Point<N>,
     },
+    /// Refers to the `Shape` stored under the given key in the `ShapeCache`,
+    /// so identical shapes share a single `ShapeHandle`.
+    Shared(String),
     #[cfg(feature = "physics3d")]
     TriMesh {
         handle: Box<dyn IntoMesh<N = N>>,
@@ -112,41 +182,727 @@ pub enum Shape<N: RealField> {
 impl<N: RealField> Shape<N> {
     /// Converts a `Shape` and its values into its corresponding `ShapeHandle`
     /// type. The `ShapeHandle` is used to define a `Collider` in the
-    /// `PhysicsWorld`.
-    fn handle(&self) -> ShapeHandle<N> {
+    /// `PhysicsWorld`. Degenerate values, such as a zero radius, NaN
+    /// coordinates or out of bounds indices, are rejected with a
+    /// `PhysicsError` instead of being passed on to ncollide. `Shape::Shared`
+    /// can only be resolved by `Shape::cached_handle`.
+    pub fn handle(&self) -> Result<ShapeHandle<N>, PhysicsError> {
+        self.build_handle(None)
+    }
+
+    /// Converts a `Shape` into its corresponding `ShapeHandle` like
+    /// `Shape::handle`, resolving `Shape::Shared` through the given
+    /// `ShapeCache`.
+    pub fn cached_handle(&self, cache: &ShapeCache<N>) -> Result<ShapeHandle<N>, PhysicsError> {
+        self.build_handle(Some(cache))
+    }
+
+    /// Returns a copy of this `Shape` scaled along each axis, e.g. by the scale
+    /// of a `Pose`. Shapes that can't be stretched non-uniformly, like a
+    /// `Shape::Ball`, use the largest factor among the affected axes instead
+    /// and log a warning. Negative factors mirror the shape along their axis.
+    /// `Shape::Shared` can only be resolved by `Shape::cached_scaled`.
+    pub fn scaled(&self, scale: &Vector<N>) -> Result<Shape<N>, PhysicsError> {
+        self.build_scaled(scale, None)
+    }
+
+    /// Scales a `Shape` like `Shape::scaled`, resolving `Shape::Shared`
+    /// through the given `ShapeCache`. The scaled copy no longer shares the
+    /// cached `ShapeHandle`.
+    pub fn cached_scaled(
+        &self,
+        scale: &Vector<N>,
+        cache: &ShapeCache<N>,
+    ) -> Result<Shape<N>, PhysicsError> {
+        self.build_scaled(scale, Some(cache))
+    }
+
+    /// Returns `true` if this `Shape`, or any part of it, refers to one of the
+    /// given `ShapeCache` keys.
+    pub(crate) fn shares_any(&self, keys: &HashSet<String>) -> bool {
         match self {
-            Shape::Ball { radius } => ShapeHandle::new(Ball::<N>::new(*radius)),
+            Shape::Compound { parts } => parts.iter().any(|part| part.1.shares_any(keys)),
+            Shape::Shared(key) => keys.contains(key),
+            _ => false,
+        }
+    }
+
+    fn build_scaled(
+        &self,
+        scale: &Vector<N>,
+        cache: Option<&ShapeCache<N>>,
+    ) -> Result<Shape<N>, PhysicsError> {
+        for factor in scale.iter() {
+            positive("Pose", "scale", factor.abs())?;
+        }
+        // symmetric shapes only depend on the magnitude of each factor, while
+        // points are mirrored along the axes with a negative factor
+        let magnitude = scale.abs();
+        let scale_point = |point: &Point<N>| Point::from(point.coords.component_mul(scale));
+        let scale_points = |points: &[Point<N>]| points.iter().map(scale_point).collect();
+
+        let scaled = match self {
+            Shape::Ball { radius } => Shape::Ball {
+                radius: *radius * factor_range("Ball", &magnitude, ALL_AXES).1,
+            },
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => Shape::Capsule {
+                half_height: *half_height * magnitude[1],
+                radius: *radius * factor_range("Capsule", &magnitude, RADIAL_AXES).1,
+            },
+            Shape::Compound { parts } => Shape::Compound {
+                parts: parts
+                    .iter()
+                    .map(|(isometry, part)| {
+                        // every axis of a rotated part is stretched by the length of its
+                        // scaled direction, which is only exact for axis aligned parts
+                        let axes = (0..scale.len())
+                            .map(|axis| {
+                                isometry.rotation
+                                    * Vector::from_fn(|i, _| match i == axis {
+                                        true => N::one(),
+                                        false => N::zero(),
+                                    })
+                            })
+                            .collect::<Vec<_>>();
+                        let aligned = axes.iter().all(|axis| {
+                            axis.iter()
+                                .filter(|x| x.abs() > N::default_epsilon())
+                                .count()
+                                == 1
+                        });
+                        let uniform = magnitude.iter().all(|factor| *factor == magnitude[0]);
+                        if !aligned && !uniform {
+                            warn!(
+                                "Rotated parts of a Compound can't be scaled non-uniformly by \
+                                 {:?}, approximating them",
+                                scale
+                            );
+                        }
+                        // a part is mirrored along its axes which end up mirrored
+                        let part_scale = Vector::from_fn(|axis, _| {
+                            let scaled = axes[axis].component_mul(scale);
+                            match axes[axis].dot(&scaled) < N::zero() {
+                                true => -scaled.norm(),
+                                false => scaled.norm(),
+                            }
+                        });
+
+                        Ok((
+                            Isometry::from_parts(
+                                Translation::from(isometry.translation.vector.component_mul(scale)),
+                                isometry.rotation,
+                            ),
+                            part.build_scaled(&part_scale, cache)?,
+                        ))
+                    })
+                    .collect::<Result<_, PhysicsError>>()?,
+            },
+            #[cfg(feature = "physics3d")]
+            Shape::Cone {
+                half_height,
+                radius,
+                subdivisions,
+            } => Shape::Cone {
+                half_height: *half_height * magnitude[1],
+                radius: *radius * factor_range("Cone", &magnitude, RADIAL_AXES).1,
+                subdivisions: *subdivisions,
+            },
+            #[cfg(feature = "physics3d")]
+            Shape::ConvexHull { points } => Shape::ConvexHull {
+                points: scale_points(points),
+            },
+            #[cfg(feature = "physics2d")]
+            Shape::ConvexPolygon { points } => Shape::ConvexPolygon {
+                points: scale_points(points),
+            },
+            Shape::Cuboid { half_extents } => Shape::Cuboid {
+                half_extents: half_extents.component_mul(&magnitude),
+            },
+            #[cfg(feature = "physics3d")]
+            Shape::Cylinder {
+                half_height,
+                radius,
+                subdivisions,
+            } => Shape::Cylinder {
+                half_height: *half_height * magnitude[1],
+                radius: *radius * factor_range("Cylinder", &magnitude, RADIAL_AXES).1,
+                subdivisions: *subdivisions,
+            },
+            Shape::HeightField {
+                heights,
+                scale: heightfield_scale,
+            } => Shape::HeightField {
+                heights: mirrored_heights(heights, scale),
+                scale: heightfield_scale.component_mul(&magnitude),
+            },
+            Shape::Plane { normal } => Shape::Plane {
+                normal: Unit::new_normalize(normal.component_div(scale)),
+            },
+            Shape::Polyline { points, indices } => Shape::Polyline {
+                points: scale_points(points),
+                indices: indices.clone(),
+            },
+            // the smallest factor keeps the rounded corners within the cuboid
+            Shape::RoundedCuboid {
+                half_extents,
+                border_radius,
+                subdivisions,
+            } => Shape::RoundedCuboid {
+                half_extents: half_extents.component_mul(&magnitude),
+                border_radius: *border_radius
+                    * factor_range("RoundedCuboid", &magnitude, ALL_AXES).0,
+                subdivisions: *subdivisions,
+            },
+            Shape::Segment { a, b } => Shape::Segment {
+                a: scale_point(a),
+                b: scale_point(b),
+            },
+            Shape::Shared(key) => cache
+                .and_then(|cache| cache.get(key))
+                .ok_or_else(|| PhysicsError::UnknownSharedShape { key: key.clone() })?
+                .build_scaled(scale, cache)?,
+            #[cfg(feature = "physics3d")]
+            Shape::TriMesh { handle } => Shape::TriMesh {
+                handle: Box::new(ScaledMesh {
+                    mesh: handle.clone(),
+                    scale: *scale,
+                }),
+            },
+            Shape::Triangle { a, b, c } => Shape::Triangle {
+                a: scale_point(a),
+                b: scale_point(b),
+                c: scale_point(c),
+            },
+        };
+
+        Ok(scaled)
+    }
+
+    fn build_handle(&self, cache: Option<&ShapeCache<N>>) -> Result<ShapeHandle<N>, PhysicsError> {
+        let handle = match self {
+            Shape::Ball { radius } => {
+                ShapeHandle::new(Ball::<N>::new(positive("Ball", "radius", *radius)?))
+            }
             Shape::Capsule {
                 half_height,
                 radius,
-            } => ShapeHandle::new(Capsule::new(*half_height, *radius)),
-            Shape::Compound { parts } => ShapeHandle::new(Compound::new(
-                parts.iter().map(|part| (part.0, part.1.handle())).collect(),
+            } => ShapeHandle::new(Capsule::new(
+                positive("Capsule", "half_height", *half_height)?,
+                positive("Capsule", "radius", *radius)?,
             )),
+            Shape::Compound { parts } => {
+                if parts.is_empty() {
+                    return Err(PhysicsError::EmptyCompound);
+                }
+
+                ShapeHandle::new(Compound::new(
+                    parts
+                        .iter()
+                        .map(|part| Ok((part.0, part.1.build_handle(cache)?)))
+                        .collect::<Result<_, PhysicsError>>()?,
+                ))
+            }
             #[cfg(feature = "physics3d")]
-            Shape::ConvexHull { points } => ShapeHandle::new(
-                ConvexHull::try_from_points(&points)
-                    .expect("Failed to generate Convex Hull from points."),
-            ),
-            Shape::Cuboid { half_extents } => ShapeHandle::new(Cuboid::new(*half_extents)),
+            Shape::Cone {
+                half_height,
+                radius,
+                subdivisions,
+            } => {
+                let half_height = positive("Cone", "half_height", *half_height)?;
+                let radius = positive("Cone", "radius", *radius)?;
+                convex_handle(
+                    "Cone",
+                    &procedural::cone(
+                        radius + radius,
+                        half_height + half_height,
+                        subdivided("Cone", *subdivisions)?,
+                    )
+                    .coords,
+                )?
+            }
+            #[cfg(feature = "physics3d")]
+            Shape::ConvexHull { points } => convex_handle("ConvexHull", points)?,
+            #[cfg(feature = "physics2d")]
+            Shape::ConvexPolygon { points } => convex_handle("ConvexPolygon", points)?,
+            Shape::Cuboid { half_extents } => {
+                for half_extent in half_extents.iter() {
+                    positive("Cuboid", "half_extents", *half_extent)?;
+                }
+                ShapeHandle::new(Cuboid::new(*half_extents))
+            }
+            #[cfg(feature = "physics3d")]
+            Shape::Cylinder {
+                half_height,
+                radius,
+                subdivisions,
+            } => {
+                let half_height = positive("Cylinder", "half_height", *half_height)?;
+                let radius = positive("Cylinder", "radius", *radius)?;
+                convex_handle(
+                    "Cylinder",
+                    &procedural::cylinder(
+                        radius + radius,
+                        half_height + half_height,
+                        subdivided("Cylinder", *subdivisions)?,
+                    )
+                    .coords,
+                )?
+            }
             Shape::HeightField { heights, scale } => {
+                // a heightfield needs at least two samples along each of its axes
+                #[cfg(feature = "physics3d")]
+                let (enough_samples, expected, actual) = (
+                    heights.nrows() >= 2 && heights.ncols() >= 2,
+                    "at least 2x2 samples",
+                    format!("{}x{}", heights.nrows(), heights.ncols()),
+                );
+                #[cfg(feature = "physics2d")]
+                let (enough_samples, expected, actual) = (
+                    heights.len() >= 2,
+                    "at least 2 samples",
+                    heights.len().to_string(),
+                );
+                if !enough_samples {
+                    return Err(PhysicsError::InvalidSize {
+                        shape: "HeightField",
+                        expected: expected.to_string(),
+                        actual,
+                    });
+                }
+                if !heights.iter().all(|height| height.is_finite()) {
+                    return Err(PhysicsError::NonFinitePoint {
+                        shape: "HeightField",
+                    });
+                }
+                for scale in scale.iter() {
+                    positive("HeightField", "scale", *scale)?;
+                }
                 ShapeHandle::new(HeightField::new(heights.clone(), *scale))
             }
-            Shape::Plane { normal } => ShapeHandle::new(Plane::new(*normal)),
+            Shape::Plane { normal } => {
+                if !normal.iter().all(|x| x.is_finite()) {
+                    return Err(PhysicsError::InvalidDimension {
+                        shape: "Plane",
+                        dimension: "normal",
+                    });
+                }
+                ShapeHandle::new(Plane::new(*normal))
+            }
             Shape::Polyline { points, indices } => {
+                enough_points("Polyline", points, 2)?;
+                if let Some(indices) = indices {
+                    indices_in_bounds("Polyline", indices.iter().flat_map(|x| x.iter()), points)?;
+                }
                 ShapeHandle::new(Polyline::new(points.clone(), indices.clone()))
             }
-            Shape::Segment { a, b } => ShapeHandle::new(Segment::new(*a, *b)),
+            Shape::RoundedCuboid {
+                half_extents,
+                border_radius,
+                subdivisions,
+            } => {
+                let border_radius = positive("RoundedCuboid", "border_radius", *border_radius)?;
+                for half_extent in half_extents.iter() {
+                    positive(
+                        "RoundedCuboid",
+                        "half_extents",
+                        *half_extent - border_radius,
+                    )?;
+                }
+                convex_handle(
+                    "RoundedCuboid",
+                    &rounded_cuboid_points(
+                        half_extents,
+                        border_radius,
+                        subdivided("RoundedCuboid", *subdivisions)?,
+                    ),
+                )?
+            }
+            Shape::Segment { a, b } => {
+                enough_points("Segment", &[*a, *b], 2)?;
+                ShapeHandle::new(Segment::new(*a, *b))
+            }
+            Shape::Shared(key) => cache
+                .and_then(|cache| cache.handle(key))
+                .cloned()
+                .ok_or_else(|| PhysicsError::UnknownSharedShape { key: key.clone() })?,
             #[cfg(feature = "physics3d")]
             Shape::TriMesh { handle } => {
                 let data = handle.points();
+                enough_points("TriMesh", &data.0, 3)?;
+                indices_in_bounds("TriMesh", data.1.iter().flat_map(|x| x.iter()), &data.0)?;
                 ShapeHandle::new(TriMesh::new(data.0, data.1, data.2))
             }
             #[cfg(feature = "physics3d")]
-            Shape::Triangle { a, b, c } => ShapeHandle::new(Triangle::new(*a, *b, *c)),
+            Shape::Triangle { a, b, c } => {
+                enough_points("Triangle", &[*a, *b, *c], 3)?;
+                ShapeHandle::new(Triangle::new(*a, *b, *c))
+            }
             #[cfg(feature = "physics2d")]
-            Shape::Triangle { a, b, c } => ShapeHandle::new(Polyline::new(vec![*a, *b, *c], None)),
+            Shape::Triangle { a, b, c } => convex_handle("Triangle", &[*a, *b, *c])?,
+        };
+
+        Ok(handle)
+    }
+}
+
+/// All axes, along which e.g. a `Shape::Ball` has to be scaled uniformly.
+#[cfg(feature = "physics3d")]
+const ALL_AXES: &[usize] = &[0, 1, 2];
+#[cfg(feature = "physics2d")]
+const ALL_AXES: &[usize] = &[0, 1];
+
+/// The axes perpendicular to the y axis, along which shapes built around the y
+/// axis, e.g. a `Shape::Capsule`, have to be scaled uniformly.
+#[cfg(feature = "physics3d")]
+const RADIAL_AXES: &[usize] = &[0, 2];
+#[cfg(feature = "physics2d")]
+const RADIAL_AXES: &[usize] = &[0];
+
+/// Returns the smallest and largest factor of `scale` along the given axes,
+/// warning if they differ as the `Shape` can't be scaled non-uniformly along
+/// them.
+fn factor_range<N: RealField>(shape: &'static str, scale: &Vector<N>, axes: &[usize]) -> (N, N) {
+    let max = axes
+        .iter()
+        .fold(N::zero(), |max, axis| max.max(scale[*axis]));
+    let min = axes.iter().fold(max, |min, axis| min.min(scale[*axis]));
+
+    if max - min > max * convert(1.0e-6) {
+        warn!(
+            "{} can't be scaled non-uniformly by {:?}, approximating it",
+            shape, scale
+        );
+    }
+
+    (min, max)
+}
+
+/// A mesh whose points are scaled along each axis, created when scaling a
+/// `Shape::TriMesh`.
+#[cfg(feature = "physics3d")]
+#[derive(Clone)]
+struct ScaledMesh<N: RealField> {
+    mesh: Box<dyn IntoMesh<N = N>>,
+    scale: Vector<N>,
+}
+
+#[cfg(feature = "physics3d")]
+impl<N: RealField> IntoMesh for ScaledMesh<N> {
+    type N = N;
+
+    fn points(&self) -> MeshData<N> {
+        let (points, mut indices, uvs) = self.mesh.points();
+        let points = points
+            .into_iter()
+            .map(|point| Point3::from(point.coords.component_mul(&self.scale)))
+            .collect();
+
+        // mirroring along an odd number of axes turns the triangles inside out
+        if self
+            .scale
+            .iter()
+            .filter(|factor| **factor < N::zero())
+            .count()
+            % 2
+            == 1
+        {
+            for triangle in &mut indices {
+                *triangle = Point3::new(triangle.x, triangle.z, triangle.y);
+            }
+        }
+
+        (points, indices, uvs)
+    }
+}
+
+/// Returns a copy of the samples of a `Shape::HeightField` mirrored along the
+/// axes with a negative factor, the columns running along the x axis and the
+/// rows along the z axis.
+#[cfg(feature = "physics3d")]
+fn mirrored_heights<N: RealField>(heights: &DMatrix<N>, scale: &Vector<N>) -> DMatrix<N> {
+    let (nrows, ncols) = heights.shape();
+    DMatrix::from_fn(nrows, ncols, |row, column| {
+        let row = match scale.z < N::zero() {
+            true => nrows - 1 - row,
+            false => row,
+        };
+        let column = match scale.x < N::zero() {
+            true => ncols - 1 - column,
+            false => column,
+        };
+        match scale.y < N::zero() {
+            true => -heights[(row, column)],
+            false => heights[(row, column)],
         }
+    })
+}
+
+/// Returns a copy of the samples of a `Shape::HeightField` mirrored along the
+/// axes with a negative factor.
+#[cfg(feature = "physics2d")]
+fn mirrored_heights<N: RealField>(heights: &DVector<N>, scale: &Vector<N>) -> DVector<N> {
+    let len = heights.len();
+    DVector::from_fn(len, |i, _| {
+        let i = match scale.x < N::zero() {
+            true => len - 1 - i,
+            false => i,
+        };
+        match scale.y < N::zero() {
+            true => -heights[i],
+            false => heights[i],
+        }
+    })
+}
+
+/// Ensures that a dimension of a `Shape` is positive and finite.
+fn positive<N: RealField>(
+    shape: &'static str,
+    dimension: &'static str,
+    value: N,
+) -> Result<N, PhysicsError> {
+    if value > N::zero() && value.is_finite() {
+        Ok(value)
+    } else {
+        Err(PhysicsError::InvalidDimension { shape, dimension })
+    }
+}
+
+/// Ensures that an approximated `Shape` is subdivided at least three times.
+fn subdivided(shape: &'static str, subdivisions: u32) -> Result<u32, PhysicsError> {
+    if subdivisions >= 3 {
+        Ok(subdivisions)
+    } else {
+        Err(PhysicsError::InvalidDimension {
+            shape,
+            dimension: "subdivisions",
+        })
+    }
+}
+
+/// Ensures that a `Shape` has at least `required` points, all of them finite.
+fn enough_points<N: RealField>(
+    shape: &'static str,
+    points: &[Point<N>],
+    required: usize,
+) -> Result<(), PhysicsError> {
+    if points.len() < required {
+        return Err(PhysicsError::NotEnoughPoints {
+            shape,
+            required,
+            actual: points.len(),
+        });
+    }
+
+    if points
+        .iter()
+        .all(|point| point.iter().all(|x| x.is_finite()))
+    {
+        Ok(())
+    } else {
+        Err(PhysicsError::NonFinitePoint { shape })
+    }
+}
+
+/// Ensures that all indices of a `Shape` refer to one of its points.
+fn indices_in_bounds<'a, N: RealField>(
+    shape: &'static str,
+    mut indices: impl Iterator<Item = &'a usize>,
+    points: &[Point<N>],
+) -> Result<(), PhysicsError> {
+    match indices.find(|index| **index >= points.len()) {
+        Some(index) => Err(PhysicsError::IndexOutOfBounds {
+            shape,
+            index: *index,
+            len: points.len(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Creates the `ShapeHandle` of the convex hull of the given points.
+#[cfg(feature = "physics3d")]
+fn convex_handle<N: RealField>(
+    shape: &'static str,
+    points: &[Point<N>],
+) -> Result<ShapeHandle<N>, PhysicsError> {
+    enough_points(shape, points, 4)?;
+    ConvexHull::try_from_points(points)
+        .map(ShapeHandle::new)
+        .ok_or(PhysicsError::ConvexHullFailed { shape })
+}
+
+/// Creates the `ShapeHandle` of the convex polygon enclosing the given points.
+#[cfg(feature = "physics2d")]
+fn convex_handle<N: RealField>(
+    shape: &'static str,
+    points: &[Point<N>],
+) -> Result<ShapeHandle<N>, PhysicsError> {
+    enough_points(shape, points, 3)?;
+    let polygon =
+        ConvexPolygon::try_from_points(points).ok_or(PhysicsError::ConvexHullFailed { shape })?;
+
+    // collinear points result in a degenerate hull without any area
+    let hull = polygon.points();
+    let area = (0..hull.len()).fold(N::zero(), |area, i| {
+        let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+        area + a.x * b.y - b.x * a.y
+    });
+    if hull.len() < 3 || area.abs() <= N::default_epsilon() {
+        return Err(PhysicsError::ConvexHullFailed { shape });
+    }
+
+    Ok(ShapeHandle::new(polygon))
+}
+
+/// Samples the hull of a rounded cuboid by placing a circle (or sphere) of
+/// `border_radius` in each corner of the inner cuboid.
+fn rounded_cuboid_points<N: RealField>(
+    half_extents: &Vector<N>,
+    border_radius: N,
+    subdivisions: u32,
+) -> Vec<Point<N>> {
+    let inner_half_extents = half_extents.map(|x| x - border_radius);
+
+    // start with the origin and mirror the corners along every axis
+    let mut corners = vec![Point::<N>::origin()];
+    for axis in 0..inner_half_extents.len() {
+        corners = corners
+            .into_iter()
+            .flat_map(|corner| {
+                let mut positive = corner;
+                let mut negative = corner;
+                positive[axis] = inner_half_extents[axis];
+                negative[axis] = -inner_half_extents[axis];
+                vec![positive, negative]
+            })
+            .collect();
+    }
+
+    let rounding = rounding_offsets(border_radius, subdivisions);
+    corners
+        .iter()
+        .flat_map(|corner| rounding.iter().map(move |offset| corner + offset))
+        .collect()
+}
+
+/// Samples a circle with the given radius.
+#[cfg(feature = "physics2d")]
+fn rounding_offsets<N: RealField>(radius: N, subdivisions: u32) -> Vec<Vector<N>> {
+    (0..subdivisions)
+        .map(|i| {
+            let angle: N = convert(2.0 * PI * f64::from(i) / f64::from(subdivisions));
+            Vector::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Samples a sphere with the given radius.
+#[cfg(feature = "physics3d")]
+fn rounding_offsets<N: RealField>(radius: N, subdivisions: u32) -> Vec<Vector<N>> {
+    procedural::sphere(radius + radius, subdivisions, subdivisions, false)
+        .coords
+        .into_iter()
+        .map(|point| point.coords)
+        .collect()
+}
+
+/// The `ShapeCache` resource stores `Shape` definitions under user defined keys
+/// together with the `ShapeHandle` built from them. Colliders using
+/// `Shape::Shared(key)` all share that one `ShapeHandle`, so spawning hundreds
+/// of identical meshes only builds the mesh once.
+///
+/// Replacing the definition of a key rebuilds its `ShapeHandle` and the
+/// `SyncCollidersToPhysicsSystem` swaps the shape of every `Collider` using it.
+/// Definitions referring to the replaced key through `Shape::Shared` are
+/// rebuilt along with it.
+pub struct ShapeCache<N: RealField> {
+    shapes: HashMap<String, (Shape<N>, ShapeHandle<N>)>,
+    replaced: HashSet<String>,
+}
+
+impl<N: RealField> Default for ShapeCache<N> {
+    fn default() -> Self {
+        Self {
+            shapes: HashMap::new(),
+            replaced: HashSet::new(),
+        }
+    }
+}
+
+impl<N: RealField> ShapeCache<N> {
+    /// Builds the `ShapeHandle` of the given `Shape` and stores both under
+    /// `key`, returning the previous definition if there was one. Nothing is
+    /// stored if the `Shape` is invalid.
+    pub fn insert<K: Into<String>>(
+        &mut self,
+        key: K,
+        shape: Shape<N>,
+    ) -> Result<Option<Shape<N>>, PhysicsError> {
+        let key = key.into();
+        let handle = shape.cached_handle(self)?;
+
+        let previous = self.shapes.insert(key.clone(), (shape, handle));
+        if previous.is_some() {
+            self.rebuild_dependents(key);
+        }
+
+        Ok(previous.map(|(shape, _)| shape))
+    }
+
+    /// Marks the given key as replaced and rebuilds the `ShapeHandle` of every
+    /// definition referring to it, directly or through other definitions.
+    fn rebuild_dependents(&mut self, key: String) {
+        let mut changed = vec![key];
+        while let Some(key) = changed.pop() {
+            // definitions referring to themselves are only rebuilt once
+            if !self.replaced.insert(key.clone()) {
+                continue;
+            }
+
+            let keys = [key].iter().cloned().collect();
+            let dependents = self
+                .shapes
+                .iter()
+                .filter(|(_, (shape, _))| shape.shares_any(&keys))
+                .map(|(dependent, _)| dependent.clone())
+                .collect::<Vec<_>>();
+            for dependent in dependents {
+                match self.shapes[&dependent].0.cached_handle(self) {
+                    Ok(handle) => self.shapes.get_mut(&dependent).unwrap().1 = handle,
+                    Err(err) => error!("Failed to rebuild shared shape {}: {}", dependent, err),
+                }
+                changed.push(dependent);
+            }
+        }
+    }
+
+    /// Removes the definition stored under `key`. Existing `Collider`s keep
+    /// their shape, new ones referring to `key` will fail to be created.
+    pub fn remove(&mut self, key: &str) -> Option<Shape<N>> {
+        self.shapes.remove(key).map(|(shape, _)| shape)
+    }
+
+    /// Returns the `Shape` stored under `key`.
+    pub fn get(&self, key: &str) -> Option<&Shape<N>> {
+        self.shapes.get(key).map(|(shape, _)| shape)
+    }
+
+    /// Returns the `ShapeHandle` built for the `Shape` stored under `key`.
+    pub fn handle(&self, key: &str) -> Option<&ShapeHandle<N>> {
+        self.shapes.get(key).map(|(_, handle)| handle)
+    }
+
+    /// Returns `true` if a `Shape` is stored under `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.shapes.contains_key(key)
+    }
+
+    /// Returns the keys whose definition got replaced since the last call.
+    pub(crate) fn take_replaced(&mut self) -> HashSet<String> {
+        mem::take(&mut self.replaced)
     }
 }
 
@@ -157,15 +913,38 @@ impl<N: RealField> Shape<N> {
 #[derive(Clone)]
 pub struct PhysicsCollider<N: RealField> {
     pub(crate) handle: Option<DefaultColliderHandle>,
+    /// The `ShapeHandle` of the physics `Collider` is rebuilt whenever the
+    /// `Component` is modified, so replacing the `Shape` or changing it in
+    /// place swaps the shape without recreating the `Collider`.
     pub shape: Shape<N>,
     pub offset_from_parent: Isometry<N>,
     pub density: N,
+    /// The material of the physics `Collider`. nphysics can't change the
+    /// material of an existing `Collider`, so changes only apply to colliders
+    /// built afterwards, e.g. by inserting the `Component` again.
     pub material: Option<MaterialHandle<N>>,
+    /// The name of a material registered with the `PhysicsMaterials`
+    /// resource. If set, it is used instead of `material`. Like `material`, it
+    /// is only applied when the physics `Collider` is built.
+    pub material_name: Option<String>,
     pub margin: N,
     pub collision_groups: CollisionGroups,
+    /// The name of a layer registered with the `CollisionLayers` resource. If
+    /// set, the `CollisionGroups` are derived from the layer instead of using
+    /// `collision_groups`.
+    pub collision_layer: Option<String>,
     pub linear_prediction: N,
     pub angular_prediction: N,
     pub sensor: bool,
+    /// Enables or disables continuous collision detection for this collider,
+    /// overriding `PhysicsBody::ccd_enabled` of the body it is attached to.
+    /// nphysics doesn't support per collider CCD thresholds.
+    pub ccd_enabled: Option<bool>,
+    /// Disabled colliders are excluded from collision and proximity detection
+    /// while keeping their physics `Collider` and configuration around.
+    pub enabled: bool,
+    /// The scale of the `Pose` the physics `Collider` was last built with.
+    pub(crate) scale: Vector<N>,
 }
 
 impl<N: RealField> Component for PhysicsCollider<N> {
@@ -180,30 +959,209 @@ impl<N: RealField> fmt::Debug for PhysicsCollider<N> {
              handle: {:?}, \
              offset_from_parent: {:?}, \
              density: {}, \
+             material_name: {:?}, \
              margin: {}, \
              collision_group: {:?}, \
+             collision_layer: {:?}, \
              linear_prediction: {}, \
              angular_prediction: {}, \
-             sensor: {} \
+             sensor: {}, \
+             ccd_enabled: {:?}, \
+             enabled: {} \
              }}",
             self.handle,
             self.offset_from_parent,
             self.density,
+            self.material_name,
             self.margin,
             self.collision_groups,
+            self.collision_layer,
             self.linear_prediction,
             self.angular_prediction,
             self.sensor,
+            self.ccd_enabled,
+            self.enabled,
         )?;
         Ok(())
     }
 }
 
 impl<N: RealField> PhysicsCollider<N> {
-    /// Returns the `ShapeHandle` for `shape`, taking the `margin` into
-    /// consideration.
-    pub(crate) fn shape_handle(&self) -> ShapeHandle<N> {
-        self.shape.handle()
+    /// Returns the `ShapeHandle` for `shape`, taking the scale of the `Pose`
+    /// into consideration.
+    pub(crate) fn shape_handle(
+        &self,
+        cache: &ShapeCache<N>,
+    ) -> Result<ShapeHandle<N>, PhysicsError> {
+        match self.scale == Vector::repeat(N::one()) {
+            // unscaled shapes keep sharing their cached ShapeHandle
+            true => self.shape.cached_handle(cache),
+            false => self
+                .shape
+                .cached_scaled(&self.scale, cache)?
+                .cached_handle(cache),
+        }
+    }
+
+    /// Returns the `MaterialHandle` the physics `Collider` should be built
+    /// with, if any. Fails if `material_name` refers to an unknown material.
+    pub(crate) fn material_handle(
+        &self,
+        materials: &PhysicsMaterials<N>,
+    ) -> Result<Option<MaterialHandle<N>>, PhysicsError> {
+        match &self.material_name {
+            Some(name) => materials.handle(name).map(Some),
+            None => Ok(self.material.clone()),
+        }
+    }
+
+    /// Returns `offset_from_parent` with its translation scaled by the scale
+    /// of the `Pose`.
+    pub(crate) fn scaled_offset(&self) -> Isometry<N> {
+        Isometry::from_parts(
+            Translation::from(
+                self.offset_from_parent
+                    .translation
+                    .vector
+                    .component_mul(&self.scale),
+            ),
+            self.offset_from_parent.rotation,
+        )
+    }
+
+    /// Modifies the heights of a `Shape::HeightField` within the given rows and
+    /// columns, e.g. to dig a crater. `deform` is called with the row, column
+    /// and a mutable reference to the height of each sample in the region. The
+    /// shape of the physics `Collider` is replaced without recreating it.
+    #[cfg(feature = "physics3d")]
+    pub fn deform_heights<F>(
+        &mut self,
+        rows: Range<usize>,
+        columns: Range<usize>,
+        mut deform: F,
+    ) -> Result<(), PhysicsError>
+    where
+        F: FnMut(usize, usize, &mut N),
+    {
+        match &mut self.shape {
+            Shape::HeightField { heights, .. } => {
+                region_in_bounds(&rows, heights.nrows())?;
+                region_in_bounds(&columns, heights.ncols())?;
+
+                for row in rows {
+                    for column in columns.clone() {
+                        deform(row, column, &mut heights[(row, column)]);
+                    }
+                }
+
+                Ok(())
+            }
+            _ => Err(PhysicsError::ShapeMismatch {
+                expected: "HeightField",
+            }),
+        }
+    }
+
+    /// Modifies the heights of a `Shape::HeightField` within the given columns,
+    /// e.g. to dig a crater. `deform` is called with the column and a mutable
+    /// reference to the height of each sample in the region. The shape of the
+    /// physics `Collider` is replaced without recreating it.
+    #[cfg(feature = "physics2d")]
+    pub fn deform_heights<F>(
+        &mut self,
+        columns: Range<usize>,
+        mut deform: F,
+    ) -> Result<(), PhysicsError>
+    where
+        F: FnMut(usize, &mut N),
+    {
+        match &mut self.shape {
+            Shape::HeightField { heights, .. } => {
+                region_in_bounds(&columns, heights.len())?;
+
+                for column in columns {
+                    deform(column, &mut heights[column]);
+                }
+
+                Ok(())
+            }
+            _ => Err(PhysicsError::ShapeMismatch {
+                expected: "HeightField",
+            }),
+        }
+    }
+
+    /// Returns the `CollisionGroups` the physics `Collider` should use while
+    /// attached to a body with the given `BodyStatus`. Disabled colliders and
+    /// colliders of disabled bodies are excluded from collision and proximity
+    /// detection entirely. Colliders on an unknown layer fall back to
+    /// `collision_groups`.
+    pub(crate) fn collision_groups_for(
+        &self,
+        body_status: BodyStatus,
+        layers: &CollisionLayers,
+    ) -> CollisionGroups {
+        match (self.enabled, body_status, &self.collision_layer) {
+            (false, ..) | (_, BodyStatus::Disabled, _) => CollisionGroups::new()
+                .with_membership(&[])
+                .with_whitelist(&[]),
+            (_, _, Some(layer)) => {
+                layers
+                    .collision_groups(layer, self.sensor)
+                    .unwrap_or_else(|err| {
+                        error!("Failed to apply collision layer: {}", err);
+                        self.collision_groups
+                    })
+            }
+            _ => self.collision_groups,
+        }
+    }
+}
+
+/// Ensures that a region of a `Shape::HeightField` lies within its samples.
+fn region_in_bounds(region: &Range<usize>, len: usize) -> Result<(), PhysicsError> {
+    match region.end > len {
+        true => Err(PhysicsError::IndexOutOfBounds {
+            shape: "HeightField",
+            index: region.end - 1,
+            len,
+        }),
+        false => Ok(()),
+    }
+}
+
+/// The `PhysicsColliders` `Component` attaches several `PhysicsCollider`s to
+/// the body of a single `Entity`, each with its own offset, material and sensor
+/// flag. This avoids having to create child entities with a `PhysicsParent`
+/// for every additional `Collider`. Events involving one of these colliders
+/// report the index of its definition inside this list.
+///
+/// Changing the amount of `PhysicsCollider`s recreates all physics
+/// `Collider`s of the `Entity`.
+#[derive(Clone, Debug)]
+pub struct PhysicsColliders<N: RealField>(pub Vec<PhysicsCollider<N>>);
+
+impl<N: RealField> Component for PhysicsColliders<N> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+impl<N: RealField> Deref for PhysicsColliders<N> {
+    type Target = Vec<PhysicsCollider<N>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<N: RealField> DerefMut for PhysicsColliders<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<N: RealField> From<Vec<PhysicsCollider<N>>> for PhysicsColliders<N> {
+    fn from(physics_colliders: Vec<PhysicsCollider<N>>) -> Self {
+        Self(physics_colliders)
     }
 }
 
@@ -226,11 +1184,15 @@ impl<N: RealField> PhysicsCollider<N> {
 ///     .offset_from_parent(Isometry::identity())
 ///     .density(1.2)
 ///     .material(MaterialHandle::new(BasicMaterial::default()))
+///     .material_named("ice")
 ///     .margin(0.02)
 ///     .collision_groups(CollisionGroups::default())
+///     .collision_layer("terrain")
 ///     .linear_prediction(0.001)
 ///     .angular_prediction(0.0)
 ///     .sensor(true)
+///     .ccd_enabled(true)
+///     .enabled(true)
 ///     .build();
 /// ```
 pub struct PhysicsColliderBuilder<N: RealField> {
@@ -238,11 +1200,15 @@ pub struct PhysicsColliderBuilder<N: RealField> {
     offset_from_parent: Isometry<N>,
     density: N,
     material: Option<MaterialHandle<N>>,
+    material_name: Option<String>,
     margin: N,
     collision_groups: CollisionGroups,
+    collision_layer: Option<String>,
     linear_prediction: N,
     angular_prediction: N,
     sensor: bool,
+    ccd_enabled: Option<bool>,
+    enabled: bool,
 }
 
 impl<N: RealField> From<Shape<N>> for PhysicsColliderBuilder<N> {
@@ -254,11 +1220,15 @@ impl<N: RealField> From<Shape<N>> for PhysicsColliderBuilder<N> {
             offset_from_parent: Isometry::identity(),
             density: N::zero(),
             material: None,
+            material_name: None,
             margin: ColliderDesc::default_margin(),
             collision_groups: CollisionGroups::default(),
+            collision_layer: None,
             linear_prediction: convert(0.001),
             angular_prediction: convert(PI / 180.0 * 5.0),
             sensor: false,
+            ccd_enabled: None,
+            enabled: true,
         }
     }
 }
@@ -282,6 +1252,12 @@ impl<N: RealField> PhysicsColliderBuilder<N> {
         self
     }
 
+    /// Sets the `material_name` value of the `PhysicsColliderBuilder`.
+    pub fn material_named(mut self, material_name: &str) -> Self {
+        self.material_name = Some(material_name.to_string());
+        self
+    }
+
     /// Sets the `margin` value of the `PhysicsColliderBuilder`.
     pub fn margin(mut self, margin: N) -> Self {
         self.margin = margin;
@@ -294,6 +1270,12 @@ impl<N: RealField> PhysicsColliderBuilder<N> {
         self
     }
 
+    /// Sets the `collision_layer` value of the `PhysicsColliderBuilder`.
+    pub fn collision_layer(mut self, collision_layer: &str) -> Self {
+        self.collision_layer = Some(collision_layer.to_string());
+        self
+    }
+
     /// Sets the `linear_prediction` value of the `PhysicsColliderBuilder`.
     pub fn linear_prediction(mut self, linear_prediction: N) -> Self {
         self.linear_prediction = linear_prediction;
@@ -312,6 +1294,18 @@ impl<N: RealField> PhysicsColliderBuilder<N> {
         self
     }
 
+    /// Sets the `ccd_enabled` value of the `PhysicsColliderBuilder`.
+    pub fn ccd_enabled(mut self, ccd_enabled: bool) -> Self {
+        self.ccd_enabled = Some(ccd_enabled);
+        self
+    }
+
+    /// Sets the `enabled` value of the `PhysicsColliderBuilder`.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     /// Builds the `PhysicsCollider` from the values set in the
     /// `PhysicsColliderBuilder` instance.
     pub fn build(self) -> PhysicsCollider<N> {
@@ -321,11 +1315,357 @@ impl<N: RealField> PhysicsColliderBuilder<N> {
             offset_from_parent: self.offset_from_parent,
             density: self.density,
             material: self.material,
+            material_name: self.material_name,
             margin: self.margin,
             collision_groups: self.collision_groups,
+            collision_layer: self.collision_layer,
             linear_prediction: self.linear_prediction,
             angular_prediction: self.angular_prediction,
             sensor: self.sensor,
+            ccd_enabled: self.ccd_enabled,
+            enabled: self.enabled,
+            scale: Vector::repeat(N::one()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use super::{Shape, ShapeCache};
+    use crate::error::PhysicsError;
+    use std::f32::consts::FRAC_PI_2;
+
+    use nalgebra::{DMatrix, Isometry3, Point3, Vector3};
+    use ncollide::shape::{Ball, Compound, ConvexHull};
+
+    /// Builds the given `Shape` and returns the half extents of its AABB and
+    /// the amount of points of its convex hull.
+    fn convex_hull(shape: Shape<f32>) -> (Vector3<f32>, usize) {
+        let handle = shape.handle().unwrap();
+        let aabb = handle.aabb(&Isometry3::identity());
+        let points = handle.as_shape::<ConvexHull<f32>>().unwrap().points().len();
+        (aabb.half_extents(), points)
+    }
+
+    fn assert_near(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!((a - b).norm() < 1.0e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn cone() {
+        let (half_extents, points) = convex_hull(Shape::Cone {
+            half_height: 2.0,
+            radius: 1.0,
+            subdivisions: 16,
+        });
+        assert_near(half_extents, Vector3::new(1.0, 2.0, 1.0));
+        assert_eq!(points, 17);
+
+        assert_eq!(
+            Shape::Cone {
+                half_height: 2.0,
+                radius: 1.0,
+                subdivisions: 0,
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::InvalidDimension {
+                shape: "Cone",
+                dimension: "subdivisions",
+            })
+        );
+        assert_eq!(
+            Shape::Cone {
+                half_height: 2.0,
+                radius: -1.0,
+                subdivisions: 16,
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::InvalidDimension {
+                shape: "Cone",
+                dimension: "radius",
+            })
+        );
+    }
+
+    #[test]
+    fn cylinder() {
+        let (half_extents, points) = convex_hull(Shape::Cylinder {
+            half_height: 0.5,
+            radius: 2.0,
+            subdivisions: 16,
+        });
+        assert_near(half_extents, Vector3::new(2.0, 0.5, 2.0));
+        assert_eq!(points, 32);
+
+        assert_eq!(
+            Shape::Cylinder {
+                half_height: 0.5,
+                radius: 2.0,
+                subdivisions: 0,
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::InvalidDimension {
+                shape: "Cylinder",
+                dimension: "subdivisions",
+            })
+        );
+        assert_eq!(
+            Shape::Cylinder {
+                half_height: 0.0,
+                radius: 2.0,
+                subdivisions: 16,
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::InvalidDimension {
+                shape: "Cylinder",
+                dimension: "half_height",
+            })
+        );
+    }
+
+    #[test]
+    fn mirrored_scale() {
+        let scale = Vector3::new(-2.0, 1.0, 1.0);
+
+        // symmetric shapes only use the magnitude of the scale
+        let cuboid = Shape::Cuboid {
+            half_extents: Vector3::new(1.0, 2.0, 3.0),
+        };
+        match cuboid.scaled(&scale) {
+            Ok(Shape::Cuboid { half_extents }) => {
+                assert_eq!(half_extents, Vector3::new(2.0, 2.0, 3.0))
+            }
+            _ => panic!("expected a Cuboid"),
+        }
+
+        // points are mirrored
+        let hull = Shape::ConvexHull {
+            points: vec![Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)],
+        };
+        match hull.scaled(&scale) {
+            Ok(Shape::ConvexHull { points }) => assert_eq!(
+                points,
+                vec![Point3::new(-2.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)]
+            ),
+            _ => panic!("expected a ConvexHull"),
+        }
+        let heightfield = Shape::HeightField {
+            heights: DMatrix::from_row_slice(2, 3, &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        };
+        match heightfield.scaled(&scale) {
+            Ok(Shape::HeightField { heights, scale }) => {
+                assert_eq!(
+                    heights,
+                    DMatrix::from_row_slice(2, 3, &[2.0, 1.0, 0.0, 5.0, 4.0, 3.0])
+                );
+                assert_eq!(scale, Vector3::new(2.0, 1.0, 1.0));
+            }
+            _ => panic!("expected a HeightField"),
+        }
+
+        // the z axis of the rotated part ends up along the mirrored x axis
+        let compound = Shape::Compound {
+            parts: vec![(
+                Isometry3::new(Vector3::x(), Vector3::y() * FRAC_PI_2),
+                Shape::Cuboid {
+                    half_extents: Vector3::new(1.0, 2.0, 3.0),
+                },
+            )],
+        };
+        match compound.scaled(&scale) {
+            Ok(Shape::Compound { parts }) => {
+                assert_near(parts[0].0.translation.vector, Vector3::new(-2.0, 0.0, 0.0));
+                match &parts[0].1 {
+                    Shape::Cuboid { half_extents } => {
+                        assert_near(*half_extents, Vector3::new(1.0, 2.0, 6.0))
+                    }
+                    _ => panic!("expected a Cuboid"),
+                }
+            }
+            _ => panic!("expected a Compound"),
+        }
+
+        let invalid = Some(PhysicsError::InvalidDimension {
+            shape: "Pose",
+            dimension: "scale",
+        });
+        let ball = Shape::Ball { radius: 1.0 };
+        assert_eq!(ball.scaled(&Vector3::new(0.0, 1.0, 1.0)).err(), invalid);
+        assert_eq!(
+            ball.scaled(&Vector3::new(std::f32::NAN, 1.0, 1.0)).err(),
+            invalid
+        );
+    }
+
+    #[test]
+    fn rebuild_shared_dependents() {
+        let mut cache = ShapeCache::<f32>::default();
+        cache.insert("rock", Shape::Ball { radius: 1.0 }).unwrap();
+        cache
+            .insert(
+                "pile",
+                Shape::Compound {
+                    parts: vec![(Isometry3::identity(), Shape::Shared("rock".into()))],
+                },
+            )
+            .unwrap();
+        assert!(cache.take_replaced().is_empty());
+
+        // replacing the rock rebuilds the pile as well
+        cache.insert("rock", Shape::Ball { radius: 2.0 }).unwrap();
+        let mut replaced = cache.take_replaced().into_iter().collect::<Vec<_>>();
+        replaced.sort();
+        assert_eq!(replaced, vec!["pile".to_string(), "rock".to_string()]);
+
+        let pile = cache.handle("pile").unwrap();
+        let rock = &pile.as_shape::<Compound<f32>>().unwrap().shapes()[0].1;
+        assert_eq!(rock.as_shape::<Ball<f32>>().unwrap().radius(), 2.0);
+    }
+
+    #[test]
+    fn heightfield_size() {
+        let heightfield = |rows, columns| Shape::HeightField {
+            heights: DMatrix::<f32>::zeros(rows, columns),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        };
+        assert!(heightfield(2, 2).handle().is_ok());
+        assert_eq!(
+            heightfield(1, 100).handle().err(),
+            Some(PhysicsError::InvalidSize {
+                shape: "HeightField",
+                expected: "at least 2x2 samples".to_string(),
+                actual: "1x100".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn rounded_cuboid() {
+        let (half_extents, _) = convex_hull(Shape::RoundedCuboid {
+            half_extents: Vector3::new(1.0, 2.0, 3.0),
+            border_radius: 0.5,
+            subdivisions: 8,
+        });
+        assert_near(half_extents, Vector3::new(1.0, 2.0, 3.0));
+
+        assert_eq!(
+            Shape::RoundedCuboid {
+                half_extents: Vector3::new(1.0, 2.0, 3.0),
+                border_radius: 0.5,
+                subdivisions: 0,
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::InvalidDimension {
+                shape: "RoundedCuboid",
+                dimension: "subdivisions",
+            })
+        );
+
+        // the border radius has to fit within the half extents
+        assert_eq!(
+            Shape::RoundedCuboid {
+                half_extents: Vector3::new(1.0, 2.0, 3.0),
+                border_radius: 1.0,
+                subdivisions: 8,
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::InvalidDimension {
+                shape: "RoundedCuboid",
+                dimension: "half_extents",
+            })
+        );
+    }
+}
+
+#[cfg(all(test, feature = "physics2d"))]
+mod tests {
+    use super::Shape;
+    use crate::error::PhysicsError;
+    use nalgebra::{Point2, Vector2};
+    use ncollide::shape::ConvexPolygon;
+
+    #[test]
+    fn convex_polygon() {
+        // the inner point isn't part of the hull
+        let handle = Shape::ConvexPolygon {
+            points: vec![
+                Point2::new(-1.0f32, -1.0),
+                Point2::new(1.0, -1.0),
+                Point2::new(1.0, 1.0),
+                Point2::new(-1.0, 1.0),
+                Point2::new(0.0, 0.0),
+            ],
+        }
+        .handle()
+        .unwrap();
+        assert_eq!(
+            handle
+                .as_shape::<ConvexPolygon<f32>>()
+                .unwrap()
+                .points()
+                .len(),
+            4
+        );
+
+        assert_eq!(
+            Shape::ConvexPolygon {
+                points: vec![Point2::new(0.0f32, 0.0), Point2::new(1.0, 0.0)],
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::NotEnoughPoints {
+                shape: "ConvexPolygon",
+                required: 3,
+                actual: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn triangle() {
+        assert!(Shape::Triangle {
+            a: Point2::new(0.0f32, 0.0),
+            b: Point2::new(1.0, 0.0),
+            c: Point2::new(0.0, 1.0),
+        }
+        .handle()
+        .is_ok());
+
+        // collinear points don't enclose any area
+        assert_eq!(
+            Shape::Triangle {
+                a: Point2::new(0.0f32, 0.0),
+                b: Point2::new(1.0, 0.0),
+                c: Point2::new(2.0, 0.0),
+            }
+            .handle()
+            .err(),
+            Some(PhysicsError::ConvexHullFailed { shape: "Triangle" })
+        );
+    }
+
+    #[test]
+    fn rounded_rectangle() {
+        assert!(Shape::RoundedCuboid {
+            half_extents: Vector2::new(1.0f32, 2.0),
+            border_radius: 0.5,
+            subdivisions: 8,
+        }
+        .handle()
+        .is_ok());
+        assert!(Shape::RoundedCuboid {
+            half_extents: Vector2::new(1.0f32, 2.0),
+            border_radius: 0.5,
+            subdivisions: 0,
         }
+        .handle()
+        .is_err());
     }
 }
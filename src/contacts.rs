@@ -0,0 +1,696 @@
+//! # Contacts module
+//! Hooks for modifying contacts before they are handed to the constraint
+//! solver. A `ContactModifier` registered with `Physics::add_contact_modifier`
+//! is called for every pair of colliders in contact and can discard the pair
+//! (one-way platforms), override its friction and restitution or add a
+//! tangential surface velocity (conveyor belts, escalators).
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use std::sync::{Arc, Mutex};
+//! use specs::world::Index;
+//! use specs_physics::{contacts::{ContactModifier, ContactPair, OneWayPlatforms},
+//!                     nalgebra::{Vector2, Unit}};
+//!
+//! struct Conveyor {
+//!     belt: Index,
+//! }
+//!
+//! impl ContactModifier<f32> for Conveyor {
+//!     fn modify_contacts(&mut self, pair: &mut ContactPair<f32>) {
+//!         pair.set_surface_velocity(self.belt, Vector2::new(2.0, 0.0));
+//!     }
+//! }
+//!
+//! // keep a handle to the platforms for registering them later on
+//! let platforms = Arc::new(Mutex::new(OneWayPlatforms::new()));
+//! platforms.lock().unwrap().insert(platform, Vector2::y_axis());
+//!
+//! let mut physics = world.write_resource::<Physics<f32>>();
+//! physics.add_contact_modifier(platforms.clone());
+//! physics.add_contact_modifier(Conveyor { belt: belt.id() });
+//! ```
+
+use std::{collections::HashMap,
+          sync::{Arc, Mutex}};
+
+use specs::{world::Index, Entity};
+
+use crate::colliders::collider_user_data;
+use nalgebra::{DVector, RealField, Unit};
+use ncollide::query::{ContactId, ContactManifold, TrackedContact};
+use nphysics::detection::ColliderContactManifold;
+use nphysics::material::{Material, MaterialContext, MaterialsCoefficientsTable};
+use nphysics::math::{Vector, Velocity, DIM};
+use nphysics::object::{BodyPartHandle, Collider, DefaultBodyHandle, DefaultBodySet,
+                       DefaultColliderHandle};
+use nphysics::solver::{ConstraintSet, ContactModel, ImpulseLimits, IntegrationParameters,
+                       SignoriniCoulombPyramidModel};
+
+/// A `ContactModifier` intercepts the contacts between two colliders before
+/// constraints are generated for them.
+///
+/// Modifiers are called once per step for every pair of colliders with at
+/// least one contact, in the order they were added to `Physics`. Sharing a
+/// modifier as `Arc<Mutex<M>>` allows changing it after it was added.
+pub trait ContactModifier<N: RealField>: Send + Sync + 'static {
+    /// Inspects and modifies the contacts of a single pair of colliders.
+    fn modify_contacts(&mut self, pair: &mut ContactPair<N>);
+}
+
+impl<N: RealField, M: ContactModifier<N>> ContactModifier<N> for Arc<Mutex<M>> {
+    fn modify_contacts(&mut self, pair: &mut ContactPair<N>) {
+        match self.lock() {
+            Ok(mut modifier) => modifier.modify_contacts(pair),
+            Err(poisoned) => poisoned.into_inner().modify_contacts(pair),
+        }
+    }
+}
+
+/// The `ContactPair` describes two colliders in contact, together with the
+/// modifications a `ContactModifier` wants to apply to their contacts.
+pub struct ContactPair<'a, N: RealField> {
+    /// `Index` of the `Entity` the first collider belongs to.
+    pub id1: Index,
+    /// Index of the first collider inside the `PhysicsColliders` of its
+    /// `Entity`, if it was created from one.
+    pub part1: Option<usize>,
+    pub collider1: &'a Collider<N, DefaultBodyHandle>,
+    /// Velocity of the body part the first collider is attached to.
+    pub velocity1: Velocity<N>,
+
+    /// `Index` of the `Entity` the second collider belongs to.
+    pub id2: Index,
+    /// Index of the second collider inside the `PhysicsColliders` of its
+    /// `Entity`, if it was created from one.
+    pub part2: Option<usize>,
+    pub collider2: &'a Collider<N, DefaultBodyHandle>,
+    /// Velocity of the body part the second collider is attached to.
+    pub velocity2: Velocity<N>,
+
+    /// The contacts between both colliders. Contact normals point from the
+    /// first towards the second collider.
+    pub manifold: &'a ContactManifold<N>,
+
+    /// Ignores all contacts of this pair if set to `true`.
+    pub discard: bool,
+    /// Replaces the friction coefficient combined from the materials of both
+    /// colliders.
+    pub friction: Option<N>,
+    /// Replaces the restitution coefficient combined from the materials of
+    /// both colliders.
+    pub restitution: Option<N>,
+    /// Replaces the world-space velocity of the surface of the first collider
+    /// relative to the surface of the second one. Only its tangential part has
+    /// an effect.
+    pub surface_velocity: Option<Vector<N>>,
+}
+
+impl<'a, N: RealField> ContactPair<'a, N> {
+    /// Returns the normal of the deepest contact, pointing from the first
+    /// towards the second collider.
+    pub fn normal(&self) -> Option<Unit<Vector<N>>> {
+        self.manifold
+            .deepest_contact()
+            .map(|contact| contact.contact.normal)
+    }
+
+    /// Returns the linear velocity of the second collider relative to the
+    /// first one.
+    pub fn relative_velocity(&self) -> Vector<N> {
+        self.velocity2.linear - self.velocity1.linear
+    }
+
+    /// Returns `true` if either collider belongs to the `Entity` with the
+    /// given `Index`.
+    pub fn involves(&self, id: Index) -> bool {
+        self.id1 == id || self.id2 == id
+    }
+
+    /// Sets the world-space surface velocity of the colliders of the `Entity`
+    /// with the given `Index`, making it behave like a conveyor belt. Does
+    /// nothing if the `Entity` is not part of this pair.
+    pub fn set_surface_velocity(&mut self, id: Index, velocity: Vector<N>) {
+        if self.id1 == id {
+            self.surface_velocity = Some(velocity);
+        } else if self.id2 == id {
+            self.surface_velocity = Some(-velocity);
+        }
+    }
+}
+
+/// The `OneWayPlatforms` `ContactModifier` turns the colliders of `Entity`s
+/// into platforms that only collide with colliders resting on or falling onto
+/// their "up" side; everything else passes through them.
+///
+/// Contacts are discarded if their normal points away from the up side of the
+/// platform, or if the other collider moves upwards relative to the platform.
+#[derive(Clone, Debug)]
+pub struct OneWayPlatforms<N: RealField> {
+    platforms: HashMap<Index, Unit<Vector<N>>>,
+}
+
+impl<N: RealField> OneWayPlatforms<N> {
+    /// Creates an empty set of platforms.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns the colliders of the given `Entity` into a one-way platform
+    /// which can be passed through from any side but `up`.
+    pub fn insert(&mut self, entity: Entity, up: Unit<Vector<N>>) {
+        self.platforms.insert(entity.id(), up);
+    }
+
+    /// Turns the colliders of the given `Entity` back into regular colliders.
+    pub fn remove(&mut self, entity: Entity) {
+        self.platforms.remove(&entity.id());
+    }
+
+    /// Returns `true` if the given `Entity` is a one-way platform.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.platforms.contains_key(&entity.id())
+    }
+}
+
+impl<N: RealField> Default for OneWayPlatforms<N> {
+    fn default() -> Self {
+        Self {
+            platforms: HashMap::new(),
+        }
+    }
+}
+
+impl<N: RealField> ContactModifier<N> for OneWayPlatforms<N> {
+    fn modify_contacts(&mut self, pair: &mut ContactPair<N>) {
+        let normal = match pair.normal() {
+            Some(normal) => normal.into_inner(),
+            None => return,
+        };
+
+        // orient the normal and velocity from the platform towards the other
+        // collider
+        let (up, normal, velocity) = if let Some(up) = self.platforms.get(&pair.id1) {
+            (up, normal, pair.relative_velocity())
+        } else if let Some(up) = self.platforms.get(&pair.id2) {
+            (up, -normal, -pair.relative_velocity())
+        } else {
+            return;
+        };
+
+        if normal.dot(up) <= N::zero() || velocity.dot(up) > N::zero() {
+            pair.discard = true;
+        }
+    }
+}
+
+/// The modifiers shared between `Physics` and its `ModifiedContactModel`.
+pub(crate) type ContactModifiers<N> = Arc<Mutex<Vec<Box<dyn ContactModifier<N>>>>>;
+
+/// The contact model `Physics` installs while `ContactModifier`s are
+/// registered. It wraps nphysics' `SignoriniCoulombPyramidModel`, leaving out
+/// discarded manifolds and patching the constraints generated for manifolds
+/// whose friction, restitution or surface velocity was replaced.
+pub(crate) struct ModifiedContactModel<N: RealField> {
+    modifiers: ContactModifiers<N>,
+    model: SignoriniCoulombPyramidModel<N>,
+}
+
+impl<N: RealField> ModifiedContactModel<N> {
+    pub(crate) fn new(modifiers: ContactModifiers<N>) -> Self {
+        Self {
+            modifiers,
+            model: SignoriniCoulombPyramidModel::new(),
+        }
+    }
+}
+
+/// Summarized modifications of all `ContactModifier`s for a single manifold.
+struct Modifications<N: RealField> {
+    friction: Option<N>,
+    restitution: Option<N>,
+    surface_velocity: Option<Vector<N>>,
+}
+
+impl<N: RealField> Modifications<N> {
+    fn is_empty(&self) -> bool {
+        self.friction.is_none() && self.restitution.is_none() && self.surface_velocity.is_none()
+    }
+}
+
+/// Runs the `ContactModifier`s on a manifold. Returns `None` if the manifold
+/// was discarded.
+fn modify<N: RealField>(
+    modifiers: &mut [Box<dyn ContactModifier<N>>],
+    bodies: &DefaultBodySet<N>,
+    manifold: &ColliderContactManifold<N, DefaultBodyHandle, DefaultColliderHandle>,
+) -> Option<Modifications<N>> {
+    let unmodified = Modifications {
+        friction: None,
+        restitution: None,
+        surface_velocity: None,
+    };
+
+    // colliders which weren't created by this crate can't be modified
+    let (id1, part1) = match collider_user_data(manifold.collider1) {
+        Some(data) => data,
+        None => return Some(unmodified),
+    };
+    let (id2, part2) = match collider_user_data(manifold.collider2) {
+        Some(data) => data,
+        None => return Some(unmodified),
+    };
+
+    let velocity = |handle: BodyPartHandle<DefaultBodyHandle>| {
+        bodies
+            .get(handle.0)
+            .and_then(|body| body.part(handle.1))
+            .map(|part| part.velocity())
+            .unwrap_or_else(Velocity::zero)
+    };
+    let (velocity1, velocity2) = match manifold.deepest_contact() {
+        Some(c) => (
+            velocity(manifold.body_part1(c.kinematic.feature1())),
+            velocity(manifold.body_part2(c.kinematic.feature2())),
+        ),
+        None => (Velocity::zero(), Velocity::zero()),
+    };
+
+    let mut pair = ContactPair {
+        id1,
+        part1,
+        collider1: manifold.collider1,
+        velocity1,
+        id2,
+        part2,
+        collider2: manifold.collider2,
+        velocity2,
+        manifold: manifold.manifold,
+        discard: false,
+        friction: None,
+        restitution: None,
+        surface_velocity: None,
+    };
+
+    for modifier in modifiers.iter_mut() {
+        modifier.modify_contacts(&mut pair);
+    }
+
+    match pair.discard {
+        true => None,
+        false => Some(Modifications {
+            friction: pair.friction,
+            restitution: pair.restitution,
+            surface_velocity: pair.surface_velocity,
+        }),
+    }
+}
+
+/// Returns the friction directions of a contact in the order
+/// `SignoriniCoulombPyramidModel` generates its friction constraints.
+#[cfg(feature = "physics2d")]
+fn tangent_basis<N: RealField>(normal: &Vector<N>) -> Vec<Vector<N>> {
+    vec![Vector::new(-normal.y, normal.x).normalize()]
+}
+
+/// Returns the friction directions of a contact in the order
+/// `SignoriniCoulombPyramidModel` generates its friction constraints.
+#[cfg(feature = "physics3d")]
+fn tangent_basis<N: RealField>(normal: &Vector<N>) -> Vec<Vector<N>> {
+    let tangent = match normal.x.abs() > normal.y.abs() {
+        true => Vector::new(normal.z, N::zero(), -normal.x),
+        false => Vector::new(N::zero(), -normal.z, normal.y),
+    }
+    .normalize();
+
+    vec![tangent.cross(normal), tangent]
+}
+
+/// A contact of a manifold together with the indices of the non-penetration
+/// and first friction constraint the stock contact model generated for it.
+struct ContactConstraints<'a, N: RealField> {
+    manifold: &'a ColliderContactManifold<'a, N, DefaultBodyHandle, DefaultColliderHandle>,
+    contact: &'a TrackedContact<N>,
+    ground: bool,
+    normal_id: usize,
+    friction_id: usize,
+}
+
+/// Replaces the friction, restitution and surface velocity of the constraints
+/// generated for a contact of a manifold.
+fn patch_constraints<N: RealField>(
+    parameters: &IntegrationParameters<N>,
+    coefficients: &MaterialsCoefficientsTable<N>,
+    modifications: &Modifications<N>,
+    contact: ContactConstraints<N>,
+    constraints: &mut ConstraintSet<N, DefaultBodyHandle, DefaultColliderHandle, ContactId>,
+) {
+    let ContactConstraints {
+        manifold,
+        contact: c,
+        ground,
+        normal_id,
+        friction_id,
+    } = contact;
+
+    // the properties the stock model built the constraints with
+    let props = <dyn Material<N>>::combine(
+        coefficients,
+        manifold.collider1.material(),
+        MaterialContext {
+            shape: manifold.collider1.shape(),
+            position: manifold.collider1.position(),
+            contact: c,
+            is_first: true,
+        },
+        manifold.collider2.material(),
+        MaterialContext {
+            shape: manifold.collider2.shape(),
+            position: manifold.collider2.position(),
+            contact: c,
+            is_first: false,
+        },
+    );
+    let velocity = &mut constraints.velocity;
+
+    if let Some(restitution) = modifications.restitution {
+        let rhs = match ground {
+            true => &mut velocity.unilateral_ground[normal_id].rhs,
+            false => &mut velocity.unilateral[normal_id].rhs,
+        };
+
+        // undo the predictive term and restitution to get the approaching
+        // velocity, see `SignoriniModel::build_velocity_constraint`
+        let depth = c.contact.depth + manifold.collider1.margin() + manifold.collider2.margin();
+        let predictive = match depth < N::zero() {
+            true => -depth * parameters.inv_dt(),
+            false => N::zero(),
+        };
+        let approach = (*rhs - predictive) / (N::one() + props.restitution.0);
+        if approach <= -parameters.restitution_velocity_threshold {
+            *rhs = approach * (N::one() + restitution) + predictive;
+        }
+    }
+
+    for (i, direction) in tangent_basis(&c.contact.normal).iter().enumerate() {
+        let (rhs, limits) = match ground {
+            true => {
+                let constraint = &mut velocity.bilateral_ground[friction_id + i];
+                (&mut constraint.rhs, &mut constraint.limits)
+            }
+            false => {
+                let constraint = &mut velocity.bilateral[friction_id + i];
+                (&mut constraint.rhs, &mut constraint.limits)
+            }
+        };
+
+        if let (Some(friction), ImpulseLimits::Dependent { coeff, .. }) =
+            (modifications.friction, limits)
+        {
+            *coeff = friction;
+        }
+        if let Some(surface_velocity) = modifications.surface_velocity {
+            *rhs += direction.dot(&(surface_velocity - props.surface_velocity));
+        }
+    }
+}
+
+impl<N: RealField> ContactModel<N, DefaultBodySet<N>, DefaultColliderHandle>
+    for ModifiedContactModel<N>
+{
+    fn num_velocity_constraints(
+        &self,
+        c: &ColliderContactManifold<N, DefaultBodyHandle, DefaultColliderHandle>,
+    ) -> usize {
+        ContactModel::<N, DefaultBodySet<N>, DefaultColliderHandle>::num_velocity_constraints(
+            &self.model,
+            c,
+        )
+    }
+
+    fn constraints(
+        &mut self,
+        parameters: &IntegrationParameters<N>,
+        coefficients: &MaterialsCoefficientsTable<N>,
+        bodies: &DefaultBodySet<N>,
+        ext_vels: &DVector<N>,
+        manifolds: &[ColliderContactManifold<N, DefaultBodyHandle, DefaultColliderHandle>],
+        ground_j_id: &mut usize,
+        j_id: &mut usize,
+        jacobians: &mut [N],
+        constraints: &mut ConstraintSet<N, DefaultBodyHandle, DefaultColliderHandle, ContactId>,
+    ) {
+        let mut kept = Vec::with_capacity(manifolds.len());
+        let mut modified = Vec::new();
+        {
+            let mut modifiers = match self.modifiers.lock() {
+                Ok(modifiers) => modifiers,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            for manifold in manifolds {
+                if let Some(modifications) = modify(&mut modifiers, bodies, manifold) {
+                    if !modifications.is_empty() {
+                        modified.push((kept.len(), modifications));
+                    }
+                    kept.push(manifold.clone());
+                }
+            }
+        }
+
+        let mut normal_ids = (
+            constraints.velocity.unilateral.len(),
+            constraints.velocity.unilateral_ground.len(),
+        );
+        let mut friction_ids = (
+            constraints.velocity.bilateral.len(),
+            constraints.velocity.bilateral_ground.len(),
+        );
+
+        self.model.constraints(
+            parameters,
+            coefficients,
+            bodies,
+            ext_vels,
+            &kept,
+            ground_j_id,
+            j_id,
+            jacobians,
+            constraints,
+        );
+
+        if modified.is_empty() {
+            return;
+        }
+
+        // walk the constraints in the order the stock model generated them,
+        // which relies on the layout of `SignoriniCoulombPyramidModel::constraints`
+        // in nphysics 0.12.3: manifolds and their contacts in order, skipping
+        // those whose bodies or body parts are gone, each contributing one
+        // unilateral and `DIM - 1` friction constraints, which are ground
+        // constraints if either body has no degrees of freedom; this has to be
+        // revisited when upgrading nphysics
+        let mut modified = modified.into_iter().peekable();
+        for (index, manifold) in kept.iter().enumerate() {
+            let (body1, body2) = match (bodies.get(manifold.body1()), bodies.get(manifold.body2()))
+            {
+                (Some(body1), Some(body2)) => (body1, body2),
+                _ => continue,
+            };
+            let modifications = match modified.peek() {
+                Some((modified_index, _)) if *modified_index == index => {
+                    modified.next().map(|(_, modifications)| modifications)
+                }
+                _ => None,
+            };
+            let ground = body1.status_dependent_ndofs() == 0 || body2.status_dependent_ndofs() == 0;
+
+            for c in manifold.contacts() {
+                let handle1 = manifold.body_part1(c.kinematic.feature1());
+                let handle2 = manifold.body_part2(c.kinematic.feature2());
+                if body1.part(handle1.1).is_none() || body2.part(handle2.1).is_none() {
+                    continue;
+                }
+
+                let (normal_id, friction_id) = match ground {
+                    true => (&mut normal_ids.1, &mut friction_ids.1),
+                    false => (&mut normal_ids.0, &mut friction_ids.0),
+                };
+                if let Some(modifications) = &modifications {
+                    patch_constraints(
+                        parameters,
+                        coefficients,
+                        modifications,
+                        ContactConstraints {
+                            manifold,
+                            contact: c,
+                            ground,
+                            normal_id: *normal_id,
+                            friction_id: *friction_id,
+                        },
+                        constraints,
+                    );
+                }
+                *normal_id += 1;
+                *friction_id += DIM - 1;
+            }
+        }
+    }
+
+    fn cache_impulses(
+        &mut self,
+        constraints: &ConstraintSet<N, DefaultBodyHandle, DefaultColliderHandle, ContactId>,
+    ) {
+        ContactModel::<N, DefaultBodySet<N>, DefaultColliderHandle>::cache_impulses(
+            &mut self.model,
+            constraints,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use specs::{world::Builder, World};
+
+    use super::{ContactModifier, ContactPair, OneWayPlatforms};
+    use crate::{colliders::Shape, parameters::Gravity, physics_dispatcher, Physics, PhysicsBody,
+                PhysicsBodyBuilder, PhysicsColliderBuilder, SimplePosition};
+    use nalgebra::{Isometry3, Vector3};
+    use nphysics::{algebra::Velocity3,
+                   force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
+    use specs::world::Index;
+
+    fn falling_ball(platform: bool, height: f32, velocity: f32) -> f32 {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(5.0, 0.1, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, height, 0.0,
+            )))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(false)
+                    .velocity(Velocity3::linear(0.0, velocity, 0.0))
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+
+        if platform {
+            let platforms = Arc::new(Mutex::new(OneWayPlatforms::new()));
+            platforms.lock().unwrap().insert(ground, Vector3::y_axis());
+            world
+                .write_resource::<Physics<f32>>()
+                .add_contact_modifier(platforms);
+        }
+
+        for _ in 0..120 {
+            dispatcher.dispatch(&mut world.res);
+        }
+
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        positions.get(ball).unwrap().0.translation.vector.y
+    }
+
+    #[test]
+    fn one_way_platform() {
+        // a ball falling onto a one-way platform lands on it
+        assert!(falling_ball(true, 2.0, -5.0) > 0.0);
+        // a ball moving upwards passes through it, but not through a regular
+        // collider
+        assert!(falling_ball(true, -2.0, 5.0) > 0.0);
+        assert!(falling_ball(false, -2.0, 5.0) < 0.0);
+    }
+
+    struct Conveyor {
+        belt: Index,
+        friction: f32,
+    }
+
+    impl ContactModifier<f32> for Conveyor {
+        fn modify_contacts(&mut self, pair: &mut ContactPair<f32>) {
+            pair.set_surface_velocity(self.belt, Vector3::new(2.0, 0.0, 0.0));
+            pair.friction = Some(self.friction);
+        }
+    }
+
+    fn conveyed_box(friction: f32) -> f32 {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+        world.add_resource(Gravity::<f32>(Vector3::new(0.0, -9.81, 0.0)));
+
+        let belt = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(50.0, 0.1, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        let cargo = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 0.6, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 0.5, 0.5),
+                })
+                .build(),
+            )
+            .build();
+        world
+            .write_resource::<Physics<f32>>()
+            .add_contact_modifier(Conveyor {
+                belt: belt.id(),
+                friction,
+            });
+
+        for _ in 0..60 {
+            dispatcher.dispatch(&mut world.res);
+        }
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        physics_bodies.get(cargo).unwrap().velocity.linear.x
+    }
+
+    #[test]
+    fn conveyor_belt() {
+        // the belt drags the box along, unless it is frictionless
+        assert!((conveyed_box(1.0) - 2.0).abs() < 0.1);
+        assert!(conveyed_box(0.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,429 @@
+//! # Decomposition module
+//! Approximate convex decomposition of concave meshes, so they can be used for
+//! dynamic bodies which `Shape::TriMesh` is unsuitable for.
+//!
+//! The decomposition is rather expensive, so its result can be stored on disk
+//! and loaded at startup instead of being recomputed every time.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{decomposition::{ConvexDecomposition, DecompositionParameters},
+//!                     mesh::{Mesh, MeshLoadOptions}};
+//!
+//! let vase = Mesh::merge("vase", Mesh::load_obj("assets/vase.obj", &MeshLoadOptions::default())?);
+//! let shape = ConvexDecomposition::load_or_compute(
+//!     "cache/vase.hacd",
+//!     &vase,
+//!     &DecompositionParameters::default(),
+//! )?
+//! .into_shape();
+//! ```
+
+use std::{fs, path::Path};
+
+use nalgebra::{convert, try_convert, Point3, RealField};
+use ncollide::{procedural::{IndexBuffer, TriMesh},
+               transformation};
+use nphysics::math::Isometry;
+
+use crate::{colliders::{IntoMesh, Shape},
+            error::PhysicsError};
+
+/// Identifies files written by `ConvexDecomposition::save`.
+const MAGIC: &[u8; 4] = b"SPCD";
+/// Version of the binary format, bumped whenever it changes.
+const VERSION: u32 = 1;
+
+/// The `DecompositionParameters` control how aggressively the parts of a
+/// `ConvexDecomposition` are merged.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DecompositionParameters<N: RealField> {
+    /// The maximum concavity of a single part, relative to the diagonal of the
+    /// bounding box of the mesh. Lower values yield more accurate but more
+    /// parts.
+    ///
+    /// default: `0.03`
+    pub concavity: N,
+
+    /// Parts are no longer merged once there are this many left, even if the
+    /// `concavity` would allow it.
+    ///
+    /// default: `1`
+    pub min_parts: usize,
+}
+
+impl<N: RealField> Default for DecompositionParameters<N> {
+    fn default() -> Self {
+        Self {
+            concavity: convert(0.03),
+            min_parts: 1,
+        }
+    }
+}
+
+/// A `ConvexDecomposition` holds the points of the convex parts approximating
+/// a concave mesh, computed using the HACD algorithm of ncollide.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvexDecomposition<N: RealField> {
+    pub parts: Vec<Vec<Point3<N>>>,
+    /// Fingerprint of the mesh and `DecompositionParameters` the parts were
+    /// computed from; used to detect outdated files.
+    fingerprint: u64,
+}
+
+impl<N: RealField> ConvexDecomposition<N> {
+    /// Decomposes the given mesh into convex parts.
+    pub fn compute<M>(
+        mesh: &M,
+        parameters: &DecompositionParameters<N>,
+    ) -> Result<Self, PhysicsError>
+    where
+        M: IntoMesh<N = N>,
+    {
+        let (points, indices, _) = mesh.points();
+        let fingerprint = fingerprint(&points, &indices, parameters);
+        Self::decompose(points, &indices, fingerprint, parameters)
+    }
+
+    /// Decomposes the given points and triangles, which were hashed into the
+    /// given fingerprint.
+    fn decompose(
+        points: Vec<Point3<N>>,
+        indices: &[Point3<usize>],
+        fingerprint: u64,
+        parameters: &DecompositionParameters<N>,
+    ) -> Result<Self, PhysicsError> {
+        if indices.is_empty() {
+            return Err(PhysicsError::NotEnoughPoints {
+                shape: "ConvexDecomposition",
+                required: 3,
+                actual: 0,
+            });
+        }
+        if let Some(index) = indices
+            .iter()
+            .flat_map(|triangle| triangle.iter())
+            .find(|index| **index >= points.len())
+        {
+            return Err(PhysicsError::IndexOutOfBounds {
+                shape: "ConvexDecomposition",
+                index: *index,
+                len: points.len(),
+            });
+        }
+
+        // HACD requires vertex normals to cast its concavity rays
+        let indices = indices
+            .iter()
+            .map(|triangle| Point3::new(triangle.x as u32, triangle.y as u32, triangle.z as u32))
+            .collect();
+        let mut trimesh = TriMesh::new(points, None, None, Some(IndexBuffer::Unified(indices)));
+        trimesh.recompute_normals();
+
+        let (hulls, _) = transformation::hacd(trimesh, parameters.concavity, parameters.min_parts);
+
+        // flat parts have no volume and can't be turned into a ConvexHull
+        let parts = hulls
+            .into_iter()
+            .map(|hull| hull.coords)
+            .filter(|points| points.len() >= 4)
+            .collect();
+
+        Ok(Self { parts, fingerprint })
+    }
+
+    /// Loads the decomposition stored at the given path if it was computed
+    /// from the same mesh and `DecompositionParameters`. Otherwise it is
+    /// computed and stored at the path for the next time.
+    pub fn load_or_compute<P, M>(
+        path: P,
+        mesh: &M,
+        parameters: &DecompositionParameters<N>,
+    ) -> Result<Self, PhysicsError>
+    where
+        P: AsRef<Path>,
+        M: IntoMesh<N = N>,
+    {
+        let (points, indices, _) = mesh.points();
+        let expected = fingerprint(&points, &indices, parameters);
+
+        match Self::load(&path) {
+            Ok(decomposition) if decomposition.fingerprint == expected => {
+                return Ok(decomposition);
+            }
+            Ok(_) => info!("Convex decomposition at {:?} is outdated", path.as_ref()),
+            Err(err) => info!(
+                "Convex decomposition at {:?} couldn't be loaded: {}",
+                path.as_ref(),
+                err
+            ),
+        }
+
+        let decomposition = Self::decompose(points, &indices, expected, parameters)?;
+        if let Err(err) = decomposition.save(&path) {
+            warn!(
+                "Failed to store convex decomposition at {:?}: {}",
+                path.as_ref(),
+                err
+            );
+        }
+
+        Ok(decomposition)
+    }
+
+    /// Reads a decomposition previously written by
+    /// `ConvexDecomposition::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PhysicsError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Writes the decomposition to the given path.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PhysicsError> {
+        Ok(fs::write(path, self.to_bytes())?)
+    }
+
+    /// Serializes the decomposition into a simple little endian format: a
+    /// header consisting of magic bytes, version, fingerprint and amount of
+    /// parts, followed by the amount of points and the coordinates as `f64`s
+    /// for each part.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.fingerprint.to_le_bytes());
+        bytes.extend_from_slice(&(self.parts.len() as u32).to_le_bytes());
+
+        for part in &self.parts {
+            bytes.extend_from_slice(&(part.len() as u32).to_le_bytes());
+            for coord in part.iter().flat_map(|point| point.iter()) {
+                bytes.extend_from_slice(&to_f64(*coord).to_bits().to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// Deserializes a decomposition written by
+    /// `ConvexDecomposition::to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PhysicsError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("missing magic bytes"));
+        }
+        if reader.u32()? != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let fingerprint = reader.u64()?;
+
+        let part_count = reader.u32()?;
+        let mut parts = Vec::new();
+        for _ in 0..part_count {
+            let point_count = reader.u32()?;
+            let mut points = Vec::new();
+            for _ in 0..point_count {
+                let x = reader.f64()?;
+                let y = reader.f64()?;
+                let z = reader.f64()?;
+                points.push(Point3::new(convert(x), convert(y), convert(z)));
+            }
+            parts.push(points);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(invalid("unexpected trailing data"));
+        }
+
+        Ok(Self { parts, fingerprint })
+    }
+
+    /// Turns the decomposition into a `Shape::Compound` of
+    /// `Shape::ConvexHull`s.
+    pub fn into_shape(self) -> Shape<N> {
+        Shape::Compound {
+            parts: self
+                .parts
+                .into_iter()
+                .map(|points| (Isometry::identity(), Shape::ConvexHull { points }))
+                .collect(),
+        }
+    }
+}
+
+/// Reads little endian values from the front of a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PhysicsError> {
+        if self.bytes.len() < len {
+            return Err(invalid("unexpected end of data"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, PhysicsError> {
+        let mut buffer = [0; 4];
+        buffer.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    fn u64(&mut self) -> Result<u64, PhysicsError> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    fn f64(&mut self) -> Result<f64, PhysicsError> {
+        self.u64().map(f64::from_bits)
+    }
+}
+
+/// Hashes the mesh and parameters using FNV-1a, which unlike the hasher of the
+/// standard library is guaranteed to be stable across builds.
+fn fingerprint<N: RealField>(
+    points: &[Point3<N>],
+    indices: &[Point3<usize>],
+    parameters: &DecompositionParameters<N>,
+) -> u64 {
+    let values = points
+        .iter()
+        .flat_map(|point| point.iter())
+        .map(|coord| to_f64(*coord).to_bits())
+        .chain(
+            indices
+                .iter()
+                .flat_map(|triangle| triangle.iter())
+                .map(|index| *index as u64),
+        )
+        .chain(vec![
+            to_f64(parameters.concavity).to_bits(),
+            parameters.min_parts as u64,
+        ]);
+
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in values.flat_map(|value| value.to_le_bytes().to_vec()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash
+}
+
+fn to_f64<N: RealField>(value: N) -> f64 {
+    try_convert(value).unwrap_or(std::f64::NAN)
+}
+
+fn invalid(reason: &str) -> PhysicsError {
+    PhysicsError::InvalidMesh {
+        format: "ConvexDecomposition",
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use std::{env, fs};
+
+    use super::{ConvexDecomposition, DecompositionParameters};
+    use crate::mesh::Mesh;
+    use nalgebra::Point3;
+
+    /// An L shaped prism, the outline extruded along the z axis. The sides have
+    /// a ring of vertices in the middle, as HACD measures the concavity along
+    /// the vertex normals, which are tilted by the caps at the ends.
+    fn l_shape() -> Mesh<f32> {
+        let outline = [
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ];
+        let points = [0.0, 0.5, 1.0]
+            .iter()
+            .flat_map(|z| outline.iter().map(move |(x, y)| Point3::new(*x, *y, *z)))
+            .collect();
+
+        // the caps are fanned out from the outer corner, which sees all other
+        // corners; the sides are wound to face outwards
+        let mut indices = Vec::new();
+        for i in 1..5 {
+            indices.push(Point3::new(0, i + 1, i));
+            indices.push(Point3::new(12, 12 + i, 13 + i));
+        }
+        for ring in &[0, 6] {
+            for i in 0..6 {
+                let (i, j) = (ring + i, ring + (i + 1) % 6);
+                indices.push(Point3::new(i, j, j + 6));
+                indices.push(Point3::new(i, j + 6, i + 6));
+            }
+        }
+
+        Mesh {
+            name: "L".to_string(),
+            data: (points, indices, None),
+        }
+    }
+
+    #[test]
+    fn round_trip_bytes() {
+        let decomposition = ConvexDecomposition::<f32> {
+            parts: vec![vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(0.0, 0.0, 1.0),
+            ]],
+            fingerprint: 42,
+        };
+
+        let bytes = decomposition.to_bytes();
+        assert_eq!(ConvexDecomposition::from_bytes(&bytes), Ok(decomposition));
+        assert!(ConvexDecomposition::<f32>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn decompose_l_shape() {
+        let decomposition =
+            ConvexDecomposition::compute(&l_shape(), &DecompositionParameters::default()).unwrap();
+        assert!(decomposition.parts.len() >= 2);
+        assert!(decomposition.into_shape().handle().is_ok());
+    }
+
+    #[test]
+    fn load_from_cache() {
+        let path = env::temp_dir().join("specs_physics_load_from_cache.hacd");
+        let _ = fs::remove_file(&path);
+        let mesh = l_shape();
+        let parameters = DecompositionParameters::default();
+
+        let computed = ConvexDecomposition::load_or_compute(&path, &mesh, &parameters).unwrap();
+        assert_eq!(ConvexDecomposition::load(&path), Ok(computed.clone()));
+
+        // a stored decomposition of the same mesh is loaded instead of recomputed
+        let mut stored = computed.clone();
+        stored.parts.truncate(1);
+        stored.save(&path).unwrap();
+        assert_eq!(
+            ConvexDecomposition::load_or_compute(&path, &mesh, &parameters),
+            Ok(stored)
+        );
+
+        // different parameters make the stored decomposition outdated
+        let parameters = DecompositionParameters {
+            min_parts: 2,
+            ..parameters
+        };
+        let recomputed = ConvexDecomposition::load_or_compute(&path, &mesh, &parameters).unwrap();
+        assert_ne!(recomputed.fingerprint, computed.fingerprint);
+        assert_eq!(ConvexDecomposition::load(&path), Ok(recomputed));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+//! # Error module
+//! The crate wide `PhysicsError` type returned by fallible operations.
+//!
+//! With the "amethyst" feature enabled, a `PhysicsError` converts into an
+//! `amethyst_error::Error` through its blanket `From` implementation for
+//! `std::error::Error` types, so `?` can be used within [Amethyst] code.
+//!
+//! [Amethyst]: https://amethyst.rs/
+
+use std::{error::Error, fmt, io};
+
+/// The `PhysicsError` enumerates everything that can go wrong when converting
+/// the definitions of this crate into their nphysics counterparts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PhysicsError {
+    /// A dimension of a `Shape`, e.g. a radius or half extent, is negative,
+    /// zero or not finite.
+    InvalidDimension {
+        shape: &'static str,
+        dimension: &'static str,
+    },
+    /// A `Shape` was given less points than required to construct it.
+    NotEnoughPoints {
+        shape: &'static str,
+        required: usize,
+        actual: usize,
+    },
+    /// The samples or tiles of a `Shape` don't add up to the size it requires,
+    /// e.g. a `Shape::HeightField` with a single row.
+    InvalidSize {
+        shape: &'static str,
+        expected: String,
+        actual: String,
+    },
+    /// A `Shape::Compound` has no parts.
+    EmptyCompound,
+    /// A point of a `Shape` contains NaN or infinite coordinates.
+    NonFinitePoint { shape: &'static str },
+    /// An index of a `Shape` refers to a point that does not exist.
+    IndexOutOfBounds {
+        shape: &'static str,
+        index: usize,
+        len: usize,
+    },
+    /// A `Shape::Shared` refers to a key that is not stored in the
+    /// `ShapeCache`.
+    UnknownSharedShape { key: String },
+    /// A mesh or image file could not be read.
+    Io {
+        kind: io::ErrorKind,
+        message: String,
+    },
+    /// A mesh file is malformed or uses an unsupported feature of its format.
+    InvalidMesh {
+        format: &'static str,
+        reason: String,
+    },
+    /// An image is malformed or uses an unsupported feature of its format.
+    InvalidImage {
+        format: &'static str,
+        reason: String,
+    },
+    /// An operation expected a different `Shape` variant.
+    ShapeMismatch { expected: &'static str },
+    /// The convex hull of the points of a `Shape` could not be computed,
+    /// usually because they are all collinear or coplanar.
+    ConvexHullFailed { shape: &'static str },
+    /// A collision layer is referred to by a name that was never registered
+    /// with the `CollisionLayers`.
+    UnknownCollisionLayer { name: String },
+    /// No more layers can be registered with the `CollisionLayers`.
+    TooManyCollisionLayers { max: usize },
+    /// A material is referred to by a name that was never registered with the
+    /// `PhysicsMaterials`.
+    UnknownMaterial { name: String },
+    /// A simulation parameter is out of its valid range.
+    InvalidParameter {
+        parameter: &'static str,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for PhysicsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhysicsError::InvalidDimension { shape, dimension } => write!(
+                f,
+                "{} shape has an invalid {}; it has to be positive and finite",
+                shape, dimension
+            ),
+            PhysicsError::NotEnoughPoints {
+                shape,
+                required,
+                actual,
+            } => write!(
+                f,
+                "{} shape requires at least {} points, got {}",
+                shape, required, actual
+            ),
+            PhysicsError::InvalidSize {
+                shape,
+                expected,
+                actual,
+            } => write!(f, "{} shape requires {}, got {}", shape, expected, actual),
+            PhysicsError::EmptyCompound => write!(f, "Compound shape has no parts"),
+            PhysicsError::NonFinitePoint { shape } => {
+                write!(f, "{} shape contains a point that is not finite", shape)
+            }
+            PhysicsError::IndexOutOfBounds { shape, index, len } => write!(
+                f,
+                "{} shape references point {} but only has {} points",
+                shape, index, len
+            ),
+            PhysicsError::UnknownSharedShape { key } => {
+                write!(f, "no shape is stored under the key \"{}\"", key)
+            }
+            PhysicsError::Io { message, .. } => write!(f, "failed to read file: {}", message),
+            PhysicsError::InvalidMesh { format, reason } => {
+                write!(f, "invalid {} mesh: {}", format, reason)
+            }
+            PhysicsError::InvalidImage { format, reason } => {
+                write!(f, "invalid {} image: {}", format, reason)
+            }
+            PhysicsError::ShapeMismatch { expected } => write!(f, "expected a {} shape", expected),
+            PhysicsError::ConvexHullFailed { shape } => write!(
+                f,
+                "failed to generate the convex hull of the {} shape's points",
+                shape
+            ),
+            PhysicsError::UnknownCollisionLayer { name } => {
+                write!(f, "no collision layer is registered as \"{}\"", name)
+            }
+            PhysicsError::TooManyCollisionLayers { max } => {
+                write!(f, "at most {} collision layers can be registered", max)
+            }
+            PhysicsError::UnknownMaterial { name } => {
+                write!(f, "no material is registered as \"{}\"", name)
+            }
+            PhysicsError::InvalidParameter {
+                parameter,
+                expected,
+            } => write!(f, "{} is out of range, expected {}", parameter, expected),
+        }
+    }
+}
+
+impl Error for PhysicsError {}
+
+impl From<io::Error> for PhysicsError {
+    fn from(err: io::Error) -> Self {
+        PhysicsError::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "amethyst"))]
+mod tests {
+    use super::PhysicsError;
+
+    fn fail() -> Result<(), PhysicsError> {
+        Err(PhysicsError::EmptyCompound)
+    }
+
+    #[test]
+    fn into_amethyst_error() {
+        let amethyst = || -> Result<(), amethyst_error::Error> {
+            fail()?;
+            Ok(())
+        };
+        let err = amethyst().unwrap_err();
+        assert!(err.to_string().contains("Compound shape has no parts"));
+    }
+}
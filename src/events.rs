@@ -1,9 +1,10 @@
 use specs::{Entities, Entity};
 
-use crate::colliders::collider_handle_to_entity;
+use crate::colliders::collider_handle_to_entity_part;
 use ncollide::pipeline::{ContactEvent as OldContactEvent, ProximityEvent as OldProximityEvent};
 use ncollide::query::Proximity;
-use nphysics::object::{DefaultColliderHandle, DefaultColliderSet};
+use nphysics::math::{Point, Vector};
+use nphysics::object::{BodyStatus, DefaultColliderHandle, DefaultColliderSet};
 
 use nalgebra::RealField;
 use shrev::EventChannel;
@@ -24,6 +25,13 @@ pub struct ContactEvent {
     pub collider1: Entity,
     pub collider2: Entity,
 
+    /// Index of the involved `PhysicsCollider` inside the `PhysicsColliders`
+    /// of `collider1`, if the collider was created from one.
+    pub part1: Option<usize>,
+    /// Index of the involved `PhysicsCollider` inside the `PhysicsColliders`
+    /// of `collider2`, if the collider was created from one.
+    pub part2: Option<usize>,
+
     pub contact_type: ContactType,
 }
 
@@ -44,9 +52,13 @@ impl ContactEvent {
         // create our own ContactEvent from the extracted data; mapping the
         // CollisionObjectHandles to Entities is error prone but should work as intended
         // as long as we're the only ones working directly with the nphysics World
+        let (collider1, part1) = collider_handle_to_entity_part(handle1, entities, colliders)?;
+        let (collider2, part2) = collider_handle_to_entity_part(handle2, entities, colliders)?;
         let result = Self {
-            collider1: collider_handle_to_entity(handle1, &entities, &colliders)?,
-            collider2: collider_handle_to_entity(handle2, &entities, &colliders)?,
+            collider1,
+            collider2,
+            part1,
+            part2,
             contact_type,
         };
 
@@ -66,6 +78,13 @@ pub struct ProximityEvent {
     pub collider1: Entity,
     pub collider2: Entity,
 
+    /// Index of the involved `PhysicsCollider` inside the `PhysicsColliders`
+    /// of `collider1`, if the collider was created from one.
+    pub part1: Option<usize>,
+    /// Index of the involved `PhysicsCollider` inside the `PhysicsColliders`
+    /// of `collider2`, if the collider was created from one.
+    pub part2: Option<usize>,
+
     pub prev_status: Proximity,
     pub new_status: Proximity,
 }
@@ -77,9 +96,15 @@ impl ProximityEvent {
         entities: &Entities,
         colliders: &DefaultColliderSet<N>,
     ) -> Option<Self> {
+        let (collider1, part1) =
+            collider_handle_to_entity_part(event.collider1, entities, colliders)?;
+        let (collider2, part2) =
+            collider_handle_to_entity_part(event.collider2, entities, colliders)?;
         let result = Self {
-            collider1: collider_handle_to_entity(event.collider1, entities, colliders)?,
-            collider2: collider_handle_to_entity(event.collider2, entities, colliders)?,
+            collider1,
+            collider2,
+            part1,
+            part2,
             prev_status: event.prev_status,
             new_status: event.new_status,
         };
@@ -91,3 +116,35 @@ impl ProximityEvent {
 /// `ProximityEvent` is a custom `EventChannel` type used to expose
 /// `ProximityEvent`s.
 pub type ProximityEvents = EventChannel<ProximityEvent>;
+
+/// The `BodyStatusChanged` event is emitted whenever the `BodyStatus` of a
+/// `PhysicsBody` transitions to a different value.
+#[derive(Debug, Copy, Clone)]
+pub struct BodyStatusChanged {
+    pub entity: Entity,
+
+    pub prev_status: BodyStatus,
+    pub new_status: BodyStatus,
+}
+
+/// `BodyStatusEvents` is a custom `EventChannel` type used to expose
+/// `BodyStatusChanged` events.
+pub type BodyStatusEvents = EventChannel<BodyStatusChanged>;
+
+/// The `ExplosionHit` event is emitted for every body affected by an
+/// `Explosion`, see `specs_physics::explosions`.
+#[derive(Debug, Copy, Clone)]
+pub struct ExplosionHit<N: RealField> {
+    /// The `Entity` of the affected `PhysicsBody`.
+    pub entity: Entity,
+    /// The impulse applied to the body. It is reported for static and
+    /// kinematic bodies as well, even though they aren't moved by it.
+    pub impulse: Vector<N>,
+    /// The point of the body closest to the center of the explosion, at which
+    /// the impulse was applied.
+    pub point: Point<N>,
+}
+
+/// `ExplosionHits` is a custom `EventChannel` type used to expose
+/// `ExplosionHit` events.
+pub type ExplosionHits<N> = EventChannel<ExplosionHit<N>>;
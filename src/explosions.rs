@@ -0,0 +1,135 @@
+//! # Explosions module
+//! Radial impulses for explosions, shock waves and the like.
+//!
+//! `Explosion`s pushed into the `Explosions` resource are handled by the
+//! `ExplosionSystem` before the next step: every body with a collider within
+//! the radius receives an impulse at the point of its colliders closest to the
+//! center, and an `ExplosionHit` is written to the `ExplosionHits` channel,
+//! e.g. for applying damage. Static and kinematic bodies are reported but not
+//! moved.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{explosions::{Explosion, Explosions},
+//!                     nalgebra::Point3};
+//!
+//! world
+//!     .write_resource::<Explosions<f32>>()
+//!     .push(Explosion::new(Point3::new(0.0, 1.0, 0.0), 5.0, 20.0).occlusion(true));
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use nalgebra::RealField;
+use ncollide::pipeline::CollisionGroups;
+use nphysics::math::Point;
+
+/// The `Explosion` describes a single explosion, see the `Explosions`
+/// resource.
+#[derive(Clone, Copy, Debug)]
+pub struct Explosion<N: RealField> {
+    /// The center of the explosion.
+    pub center: Point<N>,
+    /// Colliders further away from the `center` than `radius` are unaffected.
+    pub radius: N,
+    /// The magnitude of the impulse at the `center`.
+    pub impulse: N,
+    /// The impulse decreases by `(1 - distance / radius)^falloff`, so `0.0`
+    /// keeps it constant and `1.0` decreases it linearly.
+    pub falloff: N,
+    /// Only colliders interacting with these groups are affected.
+    pub groups: CollisionGroups,
+    /// Skips bodies occluded by other, non-sensor colliders between them and
+    /// the `center`.
+    pub occlusion: bool,
+}
+
+impl<N: RealField> Explosion<N> {
+    /// Creates a new `Explosion` with a linear falloff, affecting all
+    /// colliders regardless of occlusion.
+    pub fn new(center: Point<N>, radius: N, impulse: N) -> Self {
+        Self {
+            center,
+            radius,
+            impulse,
+            falloff: N::one(),
+            groups: CollisionGroups::new(),
+            occlusion: false,
+        }
+    }
+
+    /// Sets the `falloff` value of the `Explosion`.
+    pub fn falloff(mut self, falloff: N) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Sets the `groups` value of the `Explosion`.
+    pub fn groups(mut self, groups: CollisionGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Sets the `occlusion` value of the `Explosion`.
+    pub fn occlusion(mut self, occlusion: bool) -> Self {
+        self.occlusion = occlusion;
+        self
+    }
+
+    /// Returns the magnitude of the impulse at the given distance from the
+    /// `center`.
+    pub fn impulse_at(&self, distance: N) -> N {
+        if distance > self.radius {
+            return N::zero();
+        }
+        if self.radius <= N::zero() {
+            return self.impulse;
+        }
+
+        let remaining = N::one() - distance / self.radius;
+        self.impulse * remaining.max(N::zero()).powf(self.falloff)
+    }
+}
+
+/// The `Explosions` resource queues the `Explosion`s handled by the next run
+/// of the `ExplosionSystem`.
+#[derive(Clone, Debug)]
+pub struct Explosions<N: RealField>(pub Vec<Explosion<N>>);
+
+impl<N: RealField> Deref for Explosions<N> {
+    type Target = Vec<Explosion<N>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<N: RealField> DerefMut for Explosions<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<N: RealField> Default for Explosions<N> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use super::Explosion;
+    use nalgebra::Point3;
+
+    #[test]
+    fn impulse_falloff() {
+        let explosion = Explosion::new(Point3::<f32>::origin(), 4.0, 10.0);
+        assert_eq!(explosion.impulse_at(0.0), 10.0);
+        assert_eq!(explosion.impulse_at(2.0), 5.0);
+        assert_eq!(explosion.impulse_at(5.0), 0.0);
+
+        let explosion = explosion.falloff(0.0);
+        assert_eq!(explosion.impulse_at(3.0), 10.0);
+    }
+}
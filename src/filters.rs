@@ -0,0 +1,342 @@
+//! # Filters module
+//! Pair filters decide which colliders don't interact at all based on the
+//! `Entity`s they belong to, e.g. a projectile and the `Entity` that fired it
+//! or the members of the same squad; rules `CollisionGroups` can't express.
+//!
+//! Filters are consulted by the broad phase of the nphysics
+//! `GeometricalWorld`, so filtered pairs neither generate contacts nor
+//! proximities, and no `ContactEvent`s or `ProximityEvent`s are emitted for
+//! them. Pairs that already interact are separated as soon as a filter reports
+//! a change.
+//!
+//! A `PairFilter` reads the `Component`s it depends on through its own
+//! `SystemData` and is registered by adding a `SyncPairFilterToPhysicsSystem`
+//! to the `Dispatcher` before the `PhysicsStepperSystem`. The
+//! `IgnoreCollisionsWith` `Component` is built on top of the
+//! `IgnoredCollisions` filter, which is part of the default `Dispatcher`.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{filters::PairFilter, systems::SyncPairFilterToPhysicsSystem};
+//!
+//! #[derive(Default)]
+//! struct SameSquad(HashMap<Entity, u32>);
+//!
+//! impl<'a> PairFilter<'a> for SameSquad {
+//!     type SystemData = (Entities<'a>, ReadStorage<'a, Squad>);
+//!
+//!     fn update(&mut self, (entities, squads): Self::SystemData) -> bool {
+//!         let squads = (&entities, &squads)
+//!             .join()
+//!             .map(|(entity, squad)| (entity, squad.0))
+//!             .collect();
+//!         let modified = squads != self.0;
+//!         self.0 = squads;
+//!         modified
+//!     }
+//!
+//!     fn ignore_pair(&self, entity1: Entity, entity2: Entity) -> bool {
+//!         match (self.0.get(&entity1), self.0.get(&entity2)) {
+//!             (Some(squad1), Some(squad2)) => squad1 == squad2,
+//!             _ => false,
+//!         }
+//!     }
+//! }
+//!
+//! let mut dispatcher_builder = DispatcherBuilder::new().with(
+//!     SyncPairFilterToPhysicsSystem::<f32, SameSquad>::default(),
+//!     "same_squad_filter_system",
+//!     &[],
+//! );
+//! register_physics_systems::<f32, SimplePosition<f32>>(&mut dispatcher_builder);
+//! ```
+
+use std::{collections::{HashMap, HashSet},
+          sync::{Arc, Mutex, MutexGuard}};
+
+use specs::{world::Index, Component, DenseVecStorage, Entities, Entity, Join, ReadStorage,
+            SystemData};
+
+use nalgebra::RealField;
+use ncollide::{bounding_volume::AABB,
+               math::Point,
+               pipeline::{BroadPhase, BroadPhaseInterferenceHandler, BroadPhaseProxyHandle,
+                          DBVTBroadPhase},
+               query::Ray};
+use nphysics::object::DefaultColliderHandle;
+
+/// A `PairFilter` decides whether the colliders of two `Entity`s interact.
+///
+/// As the broad phase runs during the physics step, the filter can't access
+/// the `World` while it is queried. Instead, `PairFilter::update` receives the
+/// `SystemData` once per frame, before the step, and the filter keeps
+/// whatever it needs to answer `PairFilter::ignore_pair` later on.
+pub trait PairFilter<'a>: Send + 'static {
+    /// The `Component`s and resources the filter depends on.
+    type SystemData: SystemData<'a>;
+
+    /// Updates the filter from its `SystemData`. Returns `true` if decisions
+    /// of the filter may have changed, in which case all pairs already
+    /// interacting are filtered again.
+    fn update(&mut self, data: Self::SystemData) -> bool;
+
+    /// Returns `true` if the colliders of both `Entity`s must not interact.
+    fn ignore_pair(&self, entity1: Entity, entity2: Entity) -> bool;
+}
+
+/// The `IgnoreCollisionsWith` `Component` lists `Entity`s the colliders of
+/// its `Entity` don't interact with, e.g. the `Entity` that fired a
+/// projectile. Removed `Entity`s are skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IgnoreCollisionsWith(pub Vec<Entity>);
+
+impl Component for IgnoreCollisionsWith {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `PairFilter` applying the `IgnoreCollisionsWith` `Component`s.
+#[derive(Debug, Default)]
+pub struct IgnoredCollisions {
+    pairs: HashSet<(Entity, Entity)>,
+}
+
+impl<'a> PairFilter<'a> for IgnoredCollisions {
+    type SystemData = (Entities<'a>, ReadStorage<'a, IgnoreCollisionsWith>);
+
+    fn update(&mut self, data: Self::SystemData) -> bool {
+        let (entities, ignore_collisions_with) = data;
+
+        let pairs = (&entities, &ignore_collisions_with)
+            .join()
+            .flat_map(|(entity, ignored)| {
+                ignored
+                    .0
+                    .iter()
+                    .filter(|other| entities.is_alive(**other))
+                    .map(move |other| sorted_pair(entity, *other))
+            })
+            .collect::<HashSet<_>>();
+        let modified = pairs != self.pairs;
+        self.pairs = pairs;
+
+        modified
+    }
+
+    fn ignore_pair(&self, entity1: Entity, entity2: Entity) -> bool {
+        self.pairs.contains(&sorted_pair(entity1, entity2))
+    }
+}
+
+fn sorted_pair(entity1: Entity, entity2: Entity) -> (Entity, Entity) {
+    if entity1 <= entity2 {
+        (entity1, entity2)
+    } else {
+        (entity2, entity1)
+    }
+}
+
+/// Locks a `Mutex` shared with the broad phase; a panic while the lock was held
+/// leaves nothing half updated that the remaining code would trip over.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// The `PairFilter`s registered with `Physics`, shared with its broad phase.
+#[derive(Default)]
+pub(crate) struct PairFilters {
+    filters: Vec<Box<dyn Fn(Entity, Entity) -> bool + Send + Sync>>,
+    /// The `Entity` each collider belongs to.
+    entities: HashMap<DefaultColliderHandle, Entity>,
+    /// Whether a filter changed since the last broad phase update.
+    modified: bool,
+}
+
+impl PairFilters {
+    pub(crate) fn add<F>(&mut self, filter: Arc<Mutex<F>>)
+    where
+        F: for<'a> PairFilter<'a>,
+    {
+        self.filters.push(Box::new(move |entity1, entity2| {
+            <F as PairFilter<'static>>::ignore_pair(&lock(&filter), entity1, entity2)
+        }));
+        self.modified = true;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub(crate) fn set_modified(&mut self) {
+        self.modified = true;
+    }
+
+    /// Resolves the `Entity`s of all colliders, given the handles `Physics`
+    /// keeps per `Entity`.
+    pub(crate) fn update_entities<'a>(
+        &mut self,
+        entities: &Entities,
+        handles: impl Iterator<Item = (&'a Index, &'a DefaultColliderHandle)>,
+    ) {
+        self.entities.clear();
+        for (id, handle) in handles {
+            self.entities.insert(*handle, entities.entity(*id));
+        }
+    }
+
+    fn ignore_pair(&self, handle1: DefaultColliderHandle, handle2: DefaultColliderHandle) -> bool {
+        match (self.entities.get(&handle1), self.entities.get(&handle2)) {
+            (Some(entity1), Some(entity2)) => self
+                .filters
+                .iter()
+                .any(|ignore_pair| ignore_pair(*entity1, *entity2)),
+            _ => false,
+        }
+    }
+}
+
+/// The broad phase of `Physics`, which wraps the default `DBVTBroadPhase` and
+/// rejects the pairs of the `PairFilters`.
+pub(crate) struct FilteredBroadPhase<N: RealField> {
+    broad_phase: DBVTBroadPhase<N, AABB<N>, DefaultColliderHandle>,
+    filters: Arc<Mutex<PairFilters>>,
+}
+
+impl<N: RealField> FilteredBroadPhase<N> {
+    pub(crate) fn new(filters: Arc<Mutex<PairFilters>>) -> Self {
+        Self {
+            // the margin nphysics uses for its default broad phase
+            broad_phase: DBVTBroadPhase::new(nalgebra::convert(0.01)),
+            filters,
+        }
+    }
+}
+
+impl<N: RealField> BroadPhase<N, AABB<N>, DefaultColliderHandle> for FilteredBroadPhase<N> {
+    fn create_proxy(&mut self, bv: AABB<N>, data: DefaultColliderHandle) -> BroadPhaseProxyHandle {
+        self.broad_phase.create_proxy(bv, data)
+    }
+
+    fn proxy(&self, handle: BroadPhaseProxyHandle) -> Option<(&AABB<N>, &DefaultColliderHandle)> {
+        self.broad_phase.proxy(handle)
+    }
+
+    fn remove(
+        &mut self,
+        handles: &[BroadPhaseProxyHandle],
+        removal_handler: &mut dyn FnMut(&DefaultColliderHandle, &DefaultColliderHandle),
+    ) {
+        self.broad_phase.remove(handles, removal_handler)
+    }
+
+    fn deferred_set_bounding_volume(&mut self, handle: BroadPhaseProxyHandle, bv: AABB<N>) {
+        self.broad_phase.deferred_set_bounding_volume(handle, bv)
+    }
+
+    fn deferred_recompute_all_proximities_with(&mut self, handle: BroadPhaseProxyHandle) {
+        self.broad_phase
+            .deferred_recompute_all_proximities_with(handle)
+    }
+
+    fn deferred_recompute_all_proximities(&mut self) {
+        self.broad_phase.deferred_recompute_all_proximities()
+    }
+
+    fn update(&mut self, handler: &mut dyn BroadPhaseInterferenceHandler<DefaultColliderHandle>) {
+        let mut filters = lock(&self.filters);
+        if filters.is_empty() {
+            self.broad_phase.update(handler);
+            return;
+        }
+
+        // revisit all pairs after a filter changed, so pairs which are ignored now
+        // stop interacting and pairs which aren't anymore start to
+        let revisit = filters.modified;
+        if revisit {
+            self.broad_phase.deferred_recompute_all_proximities();
+            filters.modified = false;
+        }
+
+        self.broad_phase.update(&mut FilteredInterferenceHandler {
+            handler,
+            filters: &filters,
+            revisit,
+        });
+    }
+
+    fn interferences_with_bounding_volume<'a>(
+        &'a self,
+        bv: &AABB<N>,
+        out: &mut Vec<&'a DefaultColliderHandle>,
+    ) {
+        self.broad_phase.interferences_with_bounding_volume(bv, out)
+    }
+
+    fn interferences_with_ray<'a>(
+        &'a self,
+        ray: &Ray<N>,
+        out: &mut Vec<&'a DefaultColliderHandle>,
+    ) {
+        self.broad_phase.interferences_with_ray(ray, out)
+    }
+
+    fn interferences_with_point<'a>(
+        &'a self,
+        point: &Point<N>,
+        out: &mut Vec<&'a DefaultColliderHandle>,
+    ) {
+        self.broad_phase.interferences_with_point(point, out)
+    }
+}
+
+struct FilteredInterferenceHandler<'a> {
+    handler: &'a mut dyn BroadPhaseInterferenceHandler<DefaultColliderHandle>,
+    filters: &'a PairFilters,
+    revisit: bool,
+}
+
+impl<'a> BroadPhaseInterferenceHandler<DefaultColliderHandle> for FilteredInterferenceHandler<'a> {
+    fn is_interference_allowed(
+        &mut self,
+        handle1: &DefaultColliderHandle,
+        handle2: &DefaultColliderHandle,
+    ) -> bool {
+        if !self.handler.is_interference_allowed(handle1, handle2) {
+            return false;
+        }
+        let ignored = self.filters.ignore_pair(*handle1, *handle2);
+
+        // the DBVTBroadPhase keeps the pairs it once reported until their bounding
+        // volumes separate, without asking the handler again; so while revisiting,
+        // the interactions are started and stopped here; both are no-ops if the
+        // interaction is already in the requested state
+        if self.revisit {
+            if ignored {
+                self.handler.interference_stopped(handle1, handle2);
+            } else {
+                self.handler.interference_started(handle1, handle2);
+            }
+        }
+
+        !ignored
+    }
+
+    fn interference_started(
+        &mut self,
+        handle1: &DefaultColliderHandle,
+        handle2: &DefaultColliderHandle,
+    ) {
+        self.handler.interference_started(handle1, handle2)
+    }
+
+    fn interference_stopped(
+        &mut self,
+        handle1: &DefaultColliderHandle,
+        handle2: &DefaultColliderHandle,
+    ) {
+        self.handler.interference_stopped(handle1, handle2)
+    }
+}
@@ -0,0 +1,192 @@
+//! # Fluids module
+//! Buoyancy and drag for dynamic bodies within fluid volumes, e.g. water.
+//!
+//! A `FluidVolume` `Component` turns the sensor `PhysicsCollider` of its
+//! `Entity` into a fluid. Overlapping colliders are tracked through the
+//! `ProximityEvents` and the resulting forces are applied by a force generator
+//! within the nphysics step, which is kept up to date by the
+//! `SyncFluidVolumesToPhysicsSystem`.
+//!
+//! The submerged volume of a collider is approximated by the intersection of
+//! its AABB with the AABB of the fluid, so fluid volumes are best described by
+//! cuboids.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{colliders::Shape,
+//!                     fluids::FluidVolume,
+//!                     nalgebra::Vector3,
+//!                     PhysicsColliderBuilder};
+//!
+//! world
+//!     .create_entity()
+//!     .with(SimplePosition::<f32>(Isometry3::translation(0.0, -5.0, 0.0)))
+//!     .with(
+//!         PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+//!             half_extents: Vector3::new(20.0, 5.0, 20.0),
+//!         })
+//!         .sensor(true)
+//!         .build(),
+//!     )
+//!     .with(FluidVolume {
+//!         density: 1.0,
+//!         linear_drag: 0.8,
+//!         angular_drag: 0.5,
+//!         flow_velocity: Vector3::new(0.5, 0.0, 0.0),
+//!     })
+//!     .build();
+//! ```
+
+use specs::{Component, DenseVecStorage};
+
+use nalgebra::{convert, RealField};
+use ncollide::bounding_volume::AABB;
+use nphysics::force_generator::ForceGenerator;
+use nphysics::math::{Force, ForceType, Point, Vector};
+use nphysics::object::{DefaultBodyHandle, DefaultBodySet};
+use nphysics::solver::IntegrationParameters;
+
+/// The `FluidVolume` `Component` applies buoyancy and drag to dynamic bodies
+/// overlapping the sensor `PhysicsCollider` of its `Entity`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FluidVolume<N: RealField> {
+    /// Density of the fluid; the buoyancy of a collider is the weight of the
+    /// fluid it displaces.
+    pub density: N,
+    /// Decelerates the submerged part of bodies proportionally to their
+    /// velocity relative to `flow_velocity`.
+    pub linear_drag: N,
+    /// Decelerates the rotation of the submerged part of bodies proportionally
+    /// to their angular velocity.
+    pub angular_drag: N,
+    /// The velocity of the fluid itself, e.g. the current of a river.
+    pub flow_velocity: Vector<N>,
+}
+
+impl<N: RealField> Component for FluidVolume<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl<N: RealField> Default for FluidVolume<N> {
+    /// Roughly water, with the density relative to bodies with a density of
+    /// `1.0`.
+    fn default() -> Self {
+        Self {
+            density: N::one(),
+            linear_drag: convert(0.5),
+            angular_drag: convert(0.5),
+            flow_velocity: Vector::zeros(),
+        }
+    }
+}
+
+/// A collider submerged in a `FluidVolume`.
+pub(crate) struct SubmergedCollider<N: RealField> {
+    body: DefaultBodyHandle,
+    /// The buoyancy force acting on the center of the submerged volume.
+    buoyancy: Vector<N>,
+    center: Point<N>,
+    /// The submerged proportion of the collider, which scales the drag.
+    fraction: N,
+    fluid: FluidVolume<N>,
+    /// The time scale of the body, see `PhysicsTimeScale`.
+    time_scale: N,
+}
+
+impl<N: RealField> SubmergedCollider<N> {
+    /// Computes the submerged part of a collider with the given AABB, returns
+    /// `None` if it isn't submerged at all.
+    pub(crate) fn new(
+        body: DefaultBodyHandle,
+        collider_aabb: &AABB<N>,
+        fluid_aabb: &AABB<N>,
+        fluid: &FluidVolume<N>,
+        gravity: &Vector<N>,
+        time_scale: N,
+    ) -> Option<Self> {
+        let mins = collider_aabb
+            .mins()
+            .coords
+            .zip_map(&fluid_aabb.mins().coords, |a, b| a.max(b));
+        let maxs = collider_aabb
+            .maxs()
+            .coords
+            .zip_map(&fluid_aabb.maxs().coords, |a, b| a.min(b));
+        let submerged = volume(&(maxs - mins))?;
+        let total = volume(&(collider_aabb.maxs() - collider_aabb.mins()))?;
+
+        Some(Self {
+            body,
+            buoyancy: -*gravity * (fluid.density * submerged),
+            center: Point::from((mins + maxs) * convert::<f64, N>(0.5)),
+            fraction: (submerged / total).min(N::one()),
+            fluid: *fluid,
+            time_scale,
+        })
+    }
+}
+
+/// Returns the volume spanned by the given extents, or `None` if it is empty.
+fn volume<N: RealField>(extents: &Vector<N>) -> Option<N> {
+    if extents.iter().any(|extent| *extent <= N::zero()) {
+        return None;
+    }
+    Some(
+        extents
+            .iter()
+            .fold(N::one(), |volume, extent| volume * *extent),
+    )
+}
+
+/// The force generator applying buoyancy and drag, it is inserted into the
+/// `DefaultForceGeneratorSet` by the `SyncFluidVolumesToPhysicsSystem`.
+pub(crate) struct FluidGenerator<N: RealField> {
+    pub(crate) submerged: Vec<SubmergedCollider<N>>,
+}
+
+impl<N: RealField> Default for FluidGenerator<N> {
+    fn default() -> Self {
+        Self {
+            submerged: Vec::new(),
+        }
+    }
+}
+
+impl<N: RealField> ForceGenerator<N, DefaultBodySet<N>> for FluidGenerator<N> {
+    fn apply(&mut self, _: &IntegrationParameters<N>, bodies: &mut DefaultBodySet<N>) {
+        for submerged in &self.submerged {
+            let body = match bodies.get_mut(submerged.body) {
+                Some(body) if body.is_dynamic() => body,
+                _ => continue,
+            };
+            let velocity = match body.part(0) {
+                Some(part) => part.velocity(),
+                None => continue,
+            };
+
+            // the velocity of time scaled bodies is scaled as well, while their
+            // accelerations are scaled quadratically, just like the global gravity
+            let time_scale = submerged.time_scale;
+            body.apply_force_at_point(
+                0,
+                &(submerged.buoyancy * (time_scale * time_scale)),
+                &submerged.center,
+                ForceType::Force,
+                false,
+            );
+
+            let fluid = &submerged.fluid;
+            let linear = (fluid.flow_velocity * time_scale - velocity.linear)
+                * (fluid.linear_drag * submerged.fraction * time_scale);
+            let angular =
+                velocity.angular * -(fluid.angular_drag * submerged.fraction * time_scale);
+            body.apply_force(
+                0,
+                &Force::new(linear, angular),
+                ForceType::AccelerationChange,
+                false,
+            );
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! # Gravity module
+//! Local gravity fields in addition to the global `Gravity`, e.g. planets
+//! attracting bodies, repulsors pushing them away or rooms with their own
+//! direction of gravity.
+//!
+//! A `GravityField` `Component` is placed at the `Pose` of its `Entity` and
+//! affects all dynamic `PhysicsBody`s, except for those with
+//! `PhysicsBody::gravity_fields_enabled` unset. The fields are applied by a
+//! force generator within the nphysics step, which is kept up to date by the
+//! `SyncGravityFieldsToPhysicsSystem`.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{colliders::Shape,
+//!                     gravity::GravityField,
+//!                     nalgebra::Vector3};
+//!
+//! // a small planet with an inverse-square falloff
+//! world
+//!     .create_entity()
+//!     .with(SimplePosition::<f32>(Isometry3::translation(0.0, -100.0, 0.0)))
+//!     .with(GravityField::Attractor {
+//!         strength: 9.81 * 50.0 * 50.0,
+//!         falloff: 2.0,
+//!         radius: 500.0,
+//!     })
+//!     .build();
+//!
+//! // a zero-g section
+//! world
+//!     .create_entity()
+//!     .with(SimplePosition::<f32>(Isometry3::identity()))
+//!     .with(GravityField::Zone {
+//!         acceleration: Vector3::zeros(),
+//!         shape: Shape::Cuboid {
+//!             half_extents: Vector3::new(10.0, 5.0, 10.0),
+//!         },
+//!     })
+//!     .build();
+//! ```
+
+use std::fmt;
+
+use specs::{Component, DenseVecStorage, FlaggedStorage};
+
+use crate::colliders::Shape;
+use nalgebra::RealField;
+use ncollide::shape::ShapeHandle;
+use nphysics::force_generator::ForceGenerator;
+use nphysics::math::{Force, ForceType, Isometry, Point, Vector};
+use nphysics::object::{DefaultBodyHandle, DefaultBodySet};
+use nphysics::solver::IntegrationParameters;
+
+/// The `GravityField` `Component` accelerates bodies depending on their
+/// position relative to the `Pose` of its `Entity`. Fields add up with each
+/// other and with the global `Gravity`, except for `GravityField::Zone`.
+#[derive(Clone)]
+pub enum GravityField<N: RealField> {
+    /// Attracts the center of mass of bodies within `radius` with an
+    /// acceleration of `strength / distance^falloff`, e.g. `2.0` for planets.
+    Attractor { strength: N, falloff: N, radius: N },
+    /// Pushes the center of mass of bodies within `radius` away with an
+    /// acceleration of `strength / distance^falloff`.
+    Repulsor { strength: N, falloff: N, radius: N },
+    /// Replaces the global `Gravity` with `acceleration` for bodies whose
+    /// center of mass lies within `shape`. The `acceleration` is rotated along
+    /// with the `Pose`, so a zero vector creates a zero-g section and rotated
+    /// rooms allow walking on walls.
+    Zone {
+        acceleration: Vector<N>,
+        shape: Shape<N>,
+    },
+}
+
+impl<N: RealField> Component for GravityField<N> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+impl<N: RealField> fmt::Debug for GravityField<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GravityField::Attractor {
+                strength,
+                falloff,
+                radius,
+            } => write!(
+                f,
+                "Attractor {{ strength: {}, falloff: {}, radius: {} }}",
+                strength, falloff, radius
+            ),
+            GravityField::Repulsor {
+                strength,
+                falloff,
+                radius,
+            } => write!(
+                f,
+                "Repulsor {{ strength: {}, falloff: {}, radius: {} }}",
+                strength, falloff, radius
+            ),
+            // the Shape doesn't implement Debug, as meshes are trait objects
+            GravityField::Zone { acceleration, .. } => {
+                write!(f, "Zone {{ acceleration: {:?} }}", acceleration)
+            }
+        }
+    }
+}
+
+/// A `GravityField` resolved to world space.
+pub(crate) enum ActiveGravityField<N: RealField> {
+    Radial {
+        center: Point<N>,
+        strength: N,
+        falloff: N,
+        radius: N,
+    },
+    Zone {
+        isometry: Isometry<N>,
+        acceleration: Vector<N>,
+        shape: ShapeHandle<N>,
+    },
+}
+
+impl<N: RealField> ActiveGravityField<N> {
+    /// Resolves the given `GravityField` at the given `Pose` isometry; zones
+    /// require the `ShapeHandle` built from their `Shape`.
+    pub(crate) fn new(
+        field: &GravityField<N>,
+        isometry: Isometry<N>,
+        shape: Option<&ShapeHandle<N>>,
+    ) -> Option<Self> {
+        let center = Point::from(isometry.translation.vector);
+        match field {
+            GravityField::Attractor {
+                strength,
+                falloff,
+                radius,
+            } => Some(ActiveGravityField::Radial {
+                center,
+                strength: *strength,
+                falloff: *falloff,
+                radius: *radius,
+            }),
+            GravityField::Repulsor {
+                strength,
+                falloff,
+                radius,
+            } => Some(ActiveGravityField::Radial {
+                center,
+                strength: -*strength,
+                falloff: *falloff,
+                radius: *radius,
+            }),
+            GravityField::Zone { acceleration, .. } => {
+                shape.map(|shape| ActiveGravityField::Zone {
+                    isometry,
+                    acceleration: isometry.rotation * acceleration,
+                    shape: shape.clone(),
+                })
+            }
+        }
+    }
+}
+
+/// The force generator applying the `GravityField`s, it is inserted into the
+/// `DefaultForceGeneratorSet` by the `SyncGravityFieldsToPhysicsSystem`.
+pub(crate) struct GravityFieldGenerator<N: RealField> {
+    /// The global gravity, which is cancelled within zones.
+    pub(crate) gravity: Vector<N>,
+    pub(crate) fields: Vec<ActiveGravityField<N>>,
+    /// The bodies affected by the fields and the factor their accelerations
+    /// are scaled by, see `PhysicsTimeScale`.
+    pub(crate) bodies: Vec<(DefaultBodyHandle, N)>,
+}
+
+impl<N: RealField> GravityFieldGenerator<N> {
+    /// Returns the acceleration caused by all fields at the given point.
+    fn acceleration_at(&self, point: &Point<N>, gravity_enabled: bool) -> Vector<N> {
+        let mut acceleration = Vector::zeros();
+        let mut in_zone = false;
+
+        for field in &self.fields {
+            match field {
+                ActiveGravityField::Radial {
+                    center,
+                    strength,
+                    falloff,
+                    radius,
+                } => {
+                    let offset = center - point;
+                    let distance = offset.norm();
+                    // the direction is undefined at the center itself
+                    if distance > *radius || distance <= N::default_epsilon() {
+                        continue;
+                    }
+                    acceleration += offset * (*strength / (distance * distance.powf(*falloff)));
+                }
+                ActiveGravityField::Zone {
+                    isometry,
+                    acceleration: zone_acceleration,
+                    shape,
+                } => {
+                    let contains = shape
+                        .as_point_query()
+                        .map_or(false, |query| query.contains_point(isometry, point));
+                    if contains {
+                        acceleration += zone_acceleration;
+                        in_zone = true;
+                    }
+                }
+            }
+        }
+
+        if in_zone && gravity_enabled {
+            acceleration -= self.gravity;
+        }
+        acceleration
+    }
+}
+
+impl<N: RealField> Default for GravityFieldGenerator<N> {
+    fn default() -> Self {
+        Self {
+            gravity: Vector::zeros(),
+            fields: Vec::new(),
+            bodies: Vec::new(),
+        }
+    }
+}
+
+impl<N: RealField> ForceGenerator<N, DefaultBodySet<N>> for GravityFieldGenerator<N> {
+    fn apply(&mut self, _: &IntegrationParameters<N>, bodies: &mut DefaultBodySet<N>) {
+        if self.fields.is_empty() {
+            return;
+        }
+
+        for (handle, factor) in &self.bodies {
+            let body = match bodies.get_mut(*handle) {
+                Some(body) if body.is_dynamic() => body,
+                _ => continue,
+            };
+            let center_of_mass = match body.part(0) {
+                Some(part) => part.center_of_mass(),
+                None => continue,
+            };
+
+            let acceleration = self.acceleration_at(&center_of_mass, body.gravity_enabled());
+            if acceleration != Vector::zeros() {
+                body.apply_force(
+                    0,
+                    &Force::linear(acceleration * *factor),
+                    ForceType::AccelerationChange,
+                    false,
+                );
+            }
+        }
+    }
+}
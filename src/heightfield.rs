@@ -0,0 +1,173 @@
+//! # Heightfield module
+//! Construction of `Shape::HeightField`s from heightmap images, raw float files
+//! and column arrays, instead of having to fill the heights by hand.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{heightfield::HeightMap, nalgebra::Vector3};
+//!
+//! // a 512x512 terrain whose brightest pixels are 40 units high
+//! let shape = HeightMap::<f32>::load_pgm("assets/terrain.pgm")?
+//!     .into_shape(Vector3::new(512.0, 40.0, 512.0));
+//! ```
+//!
+//! Heightfield colliders can be deformed at runtime, e.g. to dig a crater,
+//! using `PhysicsCollider::deform_heights`.
+
+use nalgebra::RealField;
+use nphysics::math::Vector;
+
+use crate::colliders::Shape;
+
+#[cfg(feature = "physics3d")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "physics3d")]
+use nalgebra::{convert, DMatrix};
+
+#[cfg(feature = "physics2d")]
+use nalgebra::DVector;
+
+#[cfg(feature = "physics3d")]
+use crate::{error::PhysicsError,
+            image::{self, Bitmap}};
+
+/// A `HeightMap` holds the heights of a heightfield. Heights loaded from images
+/// are normalized to `[0, 1]`, so the size passed to `HeightMap::into_shape`
+/// determines the height of the terrain.
+///
+/// Rows of an image run along the z axis, its columns along the x axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightMap<N: RealField> {
+    #[cfg(feature = "physics3d")]
+    pub heights: DMatrix<N>,
+    #[cfg(feature = "physics2d")]
+    pub heights: DVector<N>,
+}
+
+#[cfg(feature = "physics3d")]
+impl<N: RealField> HeightMap<N> {
+    /// Loads an 8 or 16 bit grayscale PGM heightmap from the given path.
+    pub fn load_pgm<P: AsRef<Path>>(path: P) -> Result<Self, PhysicsError> {
+        Self::from_pgm_bytes(&fs::read(path)?)
+    }
+
+    /// Parses an 8 or 16 bit grayscale PGM heightmap.
+    pub fn from_pgm_bytes(bytes: &[u8]) -> Result<Self, PhysicsError> {
+        Ok(Self::from_bitmap(&image::decode_pgm(bytes)?))
+    }
+
+    /// Loads an 8 or 16 bit PNG heightmap from the given path. Colored images
+    /// use the average of their color channels.
+    #[cfg(feature = "png")]
+    pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Self, PhysicsError> {
+        Self::from_png_bytes(&fs::read(path)?)
+    }
+
+    /// Parses an 8 or 16 bit PNG heightmap. Colored images use the average of
+    /// their color channels.
+    #[cfg(feature = "png")]
+    pub fn from_png_bytes(bytes: &[u8]) -> Result<Self, PhysicsError> {
+        Ok(Self::from_bitmap(&image::decode_png(bytes)?))
+    }
+
+    /// Loads a raw heightmap of little endian `f32`s, stored row by row, from
+    /// the given path.
+    pub fn load_raw<P: AsRef<Path>>(
+        path: P,
+        rows: usize,
+        columns: usize,
+    ) -> Result<Self, PhysicsError> {
+        Self::from_raw_bytes(&fs::read(path)?, rows, columns)
+    }
+
+    /// Parses a raw heightmap of little endian `f32`s, stored row by row. The
+    /// heights are used as they are.
+    pub fn from_raw_bytes(bytes: &[u8], rows: usize, columns: usize) -> Result<Self, PhysicsError> {
+        if bytes.len() != rows * columns * 4 {
+            return Err(image::invalid(
+                "raw",
+                &format!(
+                    "expected {} bytes for {}x{} heights, got {}",
+                    rows * columns * 4,
+                    rows,
+                    columns,
+                    bytes.len()
+                ),
+            ));
+        }
+
+        let heights = bytes
+            .chunks(4)
+            .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+            .map(|height| convert::<f64, N>(f64::from(height)))
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            heights: DMatrix::from_row_slice(rows, columns, &heights),
+        })
+    }
+
+    fn from_bitmap(bitmap: &Bitmap) -> Self {
+        Self {
+            heights: DMatrix::from_fn(bitmap.height, bitmap.width, |row, column| {
+                convert(bitmap.intensity(column, row))
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "physics2d")]
+impl<N: RealField> HeightMap<N> {
+    /// Creates a `HeightMap` from the heights of equally spaced columns, from
+    /// left to right.
+    pub fn from_columns(columns: &[N]) -> Self {
+        Self {
+            heights: DVector::from_column_slice(columns),
+        }
+    }
+}
+
+impl<N: RealField> HeightMap<N> {
+    /// Turns the `HeightMap` into a `Shape::HeightField` centered around the
+    /// origin. `size` is the extent of the terrain along each axis; the height
+    /// along the y axis is the height of a sample with the value `1`.
+    pub fn into_shape(self, size: Vector<N>) -> Shape<N> {
+        Shape::HeightField {
+            heights: self.heights,
+            scale: size,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use super::HeightMap;
+
+    #[test]
+    fn from_pgm_bytes() {
+        let binary =
+            HeightMap::<f32>::from_pgm_bytes(b"P5\n# terrain\n3 2\n255\n\x00\x33\xff\x00\x00\x00")
+                .unwrap();
+        assert_eq!(binary.heights.nrows(), 2);
+        assert_eq!(binary.heights.ncols(), 3);
+        assert_eq!(binary.heights[(0, 1)], 0.2);
+        assert_eq!(binary.heights[(0, 2)], 1.0);
+
+        let ascii = HeightMap::<f32>::from_pgm_bytes(b"P2 3 2 255 0 51 255 0 0 0").unwrap();
+        assert_eq!(ascii, binary);
+    }
+
+    #[test]
+    fn from_raw_bytes() {
+        let bytes = [0.5f32, 1.0, 2.0, 4.0]
+            .iter()
+            .flat_map(|x| x.to_bits().to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let height_map = HeightMap::<f32>::from_raw_bytes(&bytes, 2, 2).unwrap();
+
+        assert_eq!(height_map.heights[(1, 0)], 2.0);
+        assert!(HeightMap::<f32>::from_raw_bytes(&bytes, 3, 2).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! Minimal decoding of the image formats used to generate shapes from, e.g.
+//! heightmaps and sprite masks.
+
+use crate::error::PhysicsError;
+
+/// A decoded image storing its samples row by row, top to bottom, with
+/// `channels` interleaved samples per pixel.
+pub(crate) struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize,
+    /// The value of a sample at full intensity.
+    pub max_value: u16,
+    pub samples: Vec<u16>,
+}
+
+impl Bitmap {
+    /// Returns the intensity of the given pixel within `[0, 1]`; the average of
+    /// its color channels, ignoring alpha.
+    #[cfg(feature = "physics3d")]
+    pub fn intensity(&self, x: usize, y: usize) -> f64 {
+        let color_channels = match self.channels {
+            2 | 4 => self.channels - 1,
+            channels => channels,
+        };
+        let pixel = (y * self.width + x) * self.channels;
+        let sum: f64 = self.samples[pixel..pixel + color_channels]
+            .iter()
+            .map(|sample| f64::from(*sample))
+            .sum();
+
+        sum / color_channels as f64 / f64::from(self.max_value)
+    }
+
+    /// Returns the opacity of the given pixel within `[0, 1]`; pixels without
+    /// alpha channel are fully opaque.
+    #[cfg(feature = "physics2d")]
+    pub fn alpha(&self, x: usize, y: usize) -> f64 {
+        match self.channels {
+            2 | 4 => {
+                let sample = self.samples[(y * self.width + x + 1) * self.channels - 1];
+                f64::from(sample) / f64::from(self.max_value)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Parses a binary (P5) or ASCII (P2) PGM image with 8 or 16 bits per sample.
+#[cfg(feature = "physics3d")]
+pub(crate) fn decode_pgm(bytes: &[u8]) -> Result<Bitmap, PhysicsError> {
+    let mut position = 0;
+    let mut header = Vec::new();
+
+    // the header consists of four whitespace separated tokens, comments start with
+    // #
+    while header.len() < 4 {
+        match bytes.get(position) {
+            Some(b'#') => {
+                while bytes.get(position).map_or(false, |byte| *byte != b'\n') {
+                    position += 1;
+                }
+            }
+            Some(byte) if byte.is_ascii_whitespace() => position += 1,
+            Some(_) => {
+                let start = position;
+                while bytes
+                    .get(position)
+                    .map_or(false, |byte| !byte.is_ascii_whitespace())
+                {
+                    position += 1;
+                }
+                header.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+            }
+            None => return Err(invalid("PGM", "incomplete header")),
+        }
+    }
+
+    let binary = match header[0].as_str() {
+        "P5" => true,
+        "P2" => false,
+        _ => {
+            return Err(invalid(
+                "PGM",
+                "only grayscale P2 and P5 images are supported",
+            ))
+        }
+    };
+    let parse = |token: &str| {
+        token
+            .parse::<usize>()
+            .map_err(|_| invalid("PGM", &format!("invalid header value \"{}\"", token)))
+    };
+    let width = parse(&header[1])?;
+    let height = parse(&header[2])?;
+    let max_value = parse(&header[3])?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("PGM", "maximum value has to be within [1, 65535]"));
+    }
+    let len = width * height;
+    if len == 0 {
+        return Err(invalid("PGM", "image is empty"));
+    }
+
+    let samples = if binary {
+        // exactly one whitespace character separates the header from the data
+        let data = &bytes[(position + 1).min(bytes.len())..];
+        match max_value {
+            0..=255 => data.iter().take(len).map(|x| u16::from(*x)).collect(),
+            _ => data
+                .chunks(2)
+                .filter(|x| x.len() == 2)
+                .take(len)
+                .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                .collect(),
+        }
+    } else {
+        String::from_utf8_lossy(&bytes[position..])
+            .split_whitespace()
+            .take(len)
+            .map(|token| {
+                token
+                    .parse::<u16>()
+                    .map_err(|_| invalid("PGM", &format!("invalid sample \"{}\"", token)))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    if samples.len() != len {
+        return Err(invalid("PGM", "not enough samples"));
+    }
+
+    Ok(Bitmap {
+        width,
+        height,
+        channels: 1,
+        max_value: max_value as u16,
+        samples,
+    })
+}
+
+/// Decodes a PNG image of any color type into 8 or 16 bits per sample.
+#[cfg(feature = "png")]
+pub(crate) fn decode_png(bytes: &[u8]) -> Result<Bitmap, PhysicsError> {
+    let mut decoder = png::Decoder::new(bytes);
+    // expands palettes, transparency chunks and sub-byte samples
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder
+        .read_info()
+        .map_err(|err| invalid("PNG", &err.to_string()))?;
+
+    let mut buffer = vec![0; info.buffer_size()];
+    reader
+        .next_frame(&mut buffer)
+        .map_err(|err| invalid("PNG", &err.to_string()))?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::RGB => 3,
+        png::ColorType::RGBA => 4,
+        png::ColorType::Indexed => return Err(invalid("PNG", "palette was not expanded")),
+    };
+    let width = info.width as usize;
+    let height = info.height as usize;
+    if width == 0 || height == 0 {
+        return Err(invalid("PNG", "image is empty"));
+    }
+    let sample_bytes = match info.bit_depth {
+        png::BitDepth::Sixteen => 2,
+        _ => 1,
+    };
+
+    // rows might be padded, so samples are read line by line
+    let mut samples = Vec::with_capacity(width * height * channels);
+    for line in buffer.chunks(info.line_size) {
+        let line = &line[..width * channels * sample_bytes];
+        match sample_bytes {
+            2 => samples.extend(line.chunks(2).map(|x| u16::from_be_bytes([x[0], x[1]]))),
+            _ => samples.extend(line.iter().map(|x| u16::from(*x))),
+        }
+    }
+
+    Ok(Bitmap {
+        width,
+        height,
+        channels,
+        max_value: match sample_bytes {
+            2 => 65535,
+            _ => 255,
+        },
+        samples,
+    })
+}
+
+pub(crate) fn invalid(format: &'static str, reason: &str) -> PhysicsError {
+    PhysicsError::InvalidImage {
+        format,
+        reason: reason.to_string(),
+    }
+}
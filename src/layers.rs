@@ -0,0 +1,224 @@
+//! # Layers module
+//! Named collision layers, so colliders don't have to hand-manage the group
+//! indices of ncollides `CollisionGroups`.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{layers::CollisionLayers, PhysicsColliderBuilder};
+//!
+//! let mut layers = CollisionLayers::default();
+//! layers.add_layer("player")?;
+//! layers.add_layer("pickup")?;
+//! // players walk through each other but still trigger pickups
+//! layers.set_collides("player", "player", false)?;
+//! layers.set_collides("player", "pickup", false)?;
+//! world.add_resource(layers);
+//!
+//! let physics_collider = PhysicsColliderBuilder::<f32>::from(shape)
+//!     .collision_layer("player")
+//!     .build();
+//! ```
+
+use ncollide::pipeline::CollisionGroups;
+
+use crate::error::PhysicsError;
+
+/// The `CollisionLayers` resource registers collision layers by name together
+/// with which of them interact. Colliders refer to a layer through
+/// `PhysicsCollider::collision_layer`; their `CollisionGroups` are derived from
+/// it by the `SyncCollidersToPhysicsSystem`, and reapplied whenever the
+/// interactions between layers are changed.
+///
+/// New layers collide and have proximity with all layers, including
+/// themselves, just like default `CollisionGroups`. The "collides with" rules
+/// apply between colliders that aren't sensors, the "proximity with" rules
+/// between a sensor and any other collider. Both are symmetric.
+///
+/// Each layer occupies two of the 30 groups ncollide supports, one for regular
+/// colliders and one for sensors, so at most `CollisionLayers::MAX_LAYERS` can
+/// be registered.
+#[derive(Clone, Debug, Default)]
+pub struct CollisionLayers {
+    names: Vec<String>,
+    /// Bit `j` of entry `i` is set if layer `i` collides with layer `j`.
+    collides: Vec<u16>,
+    /// Bit `j` of entry `i` is set if layer `i` has proximity with layer `j`.
+    proximity: Vec<u16>,
+    modified: bool,
+}
+
+impl CollisionLayers {
+    /// The maximum amount of layers that can be registered.
+    pub const MAX_LAYERS: usize = 15;
+
+    /// Registers a layer under the given name and returns its index. Adding an
+    /// already registered layer returns its existing index.
+    pub fn add_layer(&mut self, name: &str) -> Result<usize, PhysicsError> {
+        if let Some(layer) = self.layer(name) {
+            return Ok(layer);
+        }
+        if self.names.len() >= Self::MAX_LAYERS {
+            return Err(PhysicsError::TooManyCollisionLayers {
+                max: Self::MAX_LAYERS,
+            });
+        }
+
+        let layer = self.names.len();
+        let bit = 1 << layer;
+        self.names.push(name.to_string());
+        for rules in self.collides.iter_mut().chain(self.proximity.iter_mut()) {
+            *rules |= bit;
+        }
+        let all = (bit << 1) - 1;
+        self.collides.push(all);
+        self.proximity.push(all);
+        self.modified = true;
+
+        Ok(layer)
+    }
+
+    /// Returns the index of the layer registered under the given name.
+    pub fn layer(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|layer| layer == name)
+    }
+
+    /// Returns the names of all registered layers, ordered by their index.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns whether colliders of the two layers collide with each other.
+    pub fn collides(&self, a: &str, b: &str) -> Result<bool, PhysicsError> {
+        let (a, b) = (self.index(a)?, self.index(b)?);
+        Ok(self.collides[a] & (1 << b) != 0)
+    }
+
+    /// Sets whether colliders of the two layers collide with each other.
+    pub fn set_collides(&mut self, a: &str, b: &str, collides: bool) -> Result<(), PhysicsError> {
+        let (a, b) = (self.index(a)?, self.index(b)?);
+        set_rule(&mut self.collides, a, b, collides);
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Returns whether sensors of either layer detect colliders of the other.
+    pub fn has_proximity(&self, a: &str, b: &str) -> Result<bool, PhysicsError> {
+        let (a, b) = (self.index(a)?, self.index(b)?);
+        Ok(self.proximity[a] & (1 << b) != 0)
+    }
+
+    /// Sets whether sensors of either layer detect colliders of the other.
+    pub fn set_proximity(&mut self, a: &str, b: &str, proximity: bool) -> Result<(), PhysicsError> {
+        let (a, b) = (self.index(a)?, self.index(b)?);
+        set_rule(&mut self.proximity, a, b, proximity);
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Returns the `CollisionGroups` of a collider on the given layer.
+    pub fn collision_groups(
+        &self,
+        name: &str,
+        sensor: bool,
+    ) -> Result<CollisionGroups, PhysicsError> {
+        let layer = self.index(name)?;
+
+        let mut whitelist = Vec::new();
+        for other in 0..self.names.len() {
+            let bit = 1 << other;
+            if self.proximity[layer] & bit != 0 {
+                whitelist.push(sensor_group(other));
+                if sensor {
+                    whitelist.push(solid_group(other));
+                }
+            }
+            if !sensor && self.collides[layer] & bit != 0 {
+                whitelist.push(solid_group(other));
+            }
+        }
+
+        let membership = match sensor {
+            true => sensor_group(layer),
+            false => solid_group(layer),
+        };
+
+        Ok(CollisionGroups::new()
+            .with_membership(&[membership])
+            .with_whitelist(&whitelist))
+    }
+
+    /// Returns `true` if layers were added or their rules changed since the
+    /// last call.
+    pub(crate) fn take_modified(&mut self) -> bool {
+        let modified = self.modified;
+        self.modified = false;
+        modified
+    }
+
+    fn index(&self, name: &str) -> Result<usize, PhysicsError> {
+        self.layer(name)
+            .ok_or_else(|| PhysicsError::UnknownCollisionLayer {
+                name: name.to_string(),
+            })
+    }
+}
+
+fn set_rule(rules: &mut [u16], a: usize, b: usize, enabled: bool) {
+    for (layer, other) in [(a, b), (b, a)].iter() {
+        match enabled {
+            true => rules[*layer] |= 1 << other,
+            false => rules[*layer] &= !(1 << other),
+        }
+    }
+}
+
+/// The group regular colliders of a layer are a member of.
+fn solid_group(layer: usize) -> usize {
+    layer * 2
+}
+
+/// The group sensors of a layer are a member of.
+fn sensor_group(layer: usize) -> usize {
+    layer * 2 + 1
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use super::CollisionLayers;
+
+    #[test]
+    fn layer_rules() {
+        let mut layers = CollisionLayers::default();
+        layers.add_layer("player").unwrap();
+        layers.add_layer("pickup").unwrap();
+        layers.set_collides("player", "pickup", false).unwrap();
+        assert_eq!(layers.collides("pickup", "player"), Ok(false));
+
+        let player = layers.collision_groups("player", false).unwrap();
+        let pickup = layers.collision_groups("pickup", false).unwrap();
+        let trigger = layers.collision_groups("pickup", true).unwrap();
+        assert!(player.can_interact_with_groups(&player));
+        assert!(!player.can_interact_with_groups(&pickup));
+        assert!(player.can_interact_with_groups(&trigger));
+
+        layers.set_proximity("player", "pickup", false).unwrap();
+        let player = layers.collision_groups("player", false).unwrap();
+        let trigger = layers.collision_groups("pickup", true).unwrap();
+        assert!(!player.can_interact_with_groups(&trigger));
+
+        assert!(layers.collision_groups("enemy", false).is_err());
+    }
+
+    #[test]
+    fn too_many_layers() {
+        let mut layers = CollisionLayers::default();
+        for layer in 0..CollisionLayers::MAX_LAYERS {
+            layers.add_layer(&layer.to_string()).unwrap();
+        }
+        assert_eq!(layers.add_layer("0"), Ok(0));
+        assert!(layers.add_layer("overflow").is_err());
+    }
+}
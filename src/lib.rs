@@ -62,6 +62,9 @@
 //! }
 //! ```
 //!
+//! A `Pose` can optionally implement `Pose::scale`, which is applied to the
+//! shapes of its colliders.
+//!
 //! If you're using [Amethyst], you can enable the "amethyst" feature for this
 //! crate which provides a `Pose<Float>` impl for `Transform`.
 //!
@@ -96,6 +99,26 @@
 //!     .build();
 //! ```
 //!
+//! Fast bodies, e.g. projectiles, can tunnel through thin colliders within a
+//! single step. Setting `PhysicsBodyBuilder::ccd_enabled` enables continuous
+//! collision detection for their colliders; the amount of CCD substeps is part
+//! of the `PhysicsIntegrationParameters` resource. nphysics only allows
+//! toggling CCD per collider, it has no per collider thresholds or sweep
+//! settings.
+//!
+//! Adding a `specs_physics::PhysicsTimeScale` `Component` slows down or
+//! speeds up a single body, e.g. for slow motion within an area.
+//!
+//! Besides the global `Gravity`, `specs_physics::gravity::GravityField`
+//! `Component`s attract or repel bodies or replace the gravity within a zone;
+//! `PhysicsBodyBuilder::gravity_fields_enabled` opts bodies out of them.
+//! Sensor colliders with a `specs_physics::fluids::FluidVolume` apply buoyancy
+//! and drag to the bodies within them.
+//!
+//! `specs_physics::explosions::Explosion`s pushed into the `Explosions`
+//! resource apply radial impulses to the bodies around them and report each
+//! affected `Entity` through the `ExplosionHits` event channel.
+//!
 //! ##### PhysicsCollider
 //!
 //! `specs_physics::PhysicsCollider`s are the counterpart to `PhysicsBody`s.
@@ -124,17 +147,63 @@
 //!     .linear_prediction(0.001)
 //!     .angular_prediction(0.0)
 //!     .sensor(true)
+//!     .enabled(true)
 //!     .build();
 //! ```
 //!
+//! Instead of managing the group indices of `CollisionGroups` by hand, layers
+//! can be registered by name with the `specs_physics::layers::CollisionLayers`
+//! resource and assigned through `PhysicsColliderBuilder::collision_layer`.
+//! Rules depending on the `Entity`s involved, like a projectile ignoring the
+//! `Entity` that fired it, are expressed with the
+//! `specs_physics::filters::IgnoreCollisionsWith` `Component` or a custom
+//! `specs_physics::filters::PairFilter`.
+//!
+//! Materials can be registered by name with the
+//! `specs_physics::materials::PhysicsMaterials` resource and assigned through
+//! `PhysicsColliderBuilder::material_named`; it also holds the friction and
+//! restitution coefficients of specific pairs of materials.
+//!
+//! Contacts can be modified before they are resolved by registering a
+//! `specs_physics::contacts::ContactModifier` with `Physics`, e.g. to discard
+//! them for one-way platforms or to add a surface velocity for conveyor belts.
+//!
 //! To assign multiple [Collider]'s the the same body, [Entity hierarchy]
-//! can be used. This utilises [specs-hierarchy].
+//! can be used. This utilises [specs-hierarchy]; a `PhysicsCollider` is
+//! attached to the body of the nearest ancestor with a `PhysicsBody` and moves
+//! along when its `PhysicsParent` changes. Alternatively, a
+//! `specs_physics::PhysicsColliders` `Component` holding several
+//! `PhysicsCollider`s attaches all of them to the body of its own `Entity`.
+//!
+//! Identical shapes, e.g. the mesh of a rock spawned hundreds of times, can be
+//! stored once in the `specs_physics::colliders::ShapeCache` resource and
+//! referred to via `Shape::Shared(key)`; all such [Collider]'s then share the
+//! same `ShapeHandle`.
+//!
+//! Meshes for `Shape::TriMesh` and `Shape::ConvexHull` can be loaded from
+//! Wavefront OBJ and binary STL files with `specs_physics::mesh::Mesh`. As
+//! `Shape::TriMesh` is unsuitable for dynamic bodies, concave meshes can be
+//! turned into a `Shape::Compound` of convex parts using
+//! `specs_physics::decomposition::ConvexDecomposition`.
+//!
+//! `Shape::HeightField`s can be created from heightmaps using
+//! `specs_physics::heightfield::HeightMap`; PNG heightmaps require the "png"
+//! feature. `PhysicsCollider::deform_heights` modifies the heights of an
+//! existing heightfield [Collider], e.g. to dig craters.
+//!
+//! For 2D games, `specs_physics::tilemap::TileMap` generates merged
+//! colliders for tilemaps, so bodies don't snag on the edges between tiles.
+//! `specs_physics::outline::AlphaMask` traces the opaque region of sprites
+//! into a `Shape::Polyline` or a `Shape::Compound` of convex polygons.
 //!
 //! ### Systems
 //!
 //! The following `System`s currently exist and should be added to your
 //! `Dispatcher` in order:
 //!
+//! 0. `specs_hierarchy::HierarchySystem<specs_physics::PhysicsParent>` -
+//! maintains the `PhysicsHierarchy` used to resolve the body of a [Collider].
+//!
 //! 1. `specs_physics::systems::SyncBodiesToPhysicsSystem` - handles the
 //! creation, modification and removal of [RigidBody]'s based on the
 //! `PhysicsBody` `Component` and an implementation of the `Pose`
@@ -143,17 +212,39 @@
 //! 2. `specs_physics::systems::SyncCollidersToPhysicsSystem` - handles
 //! the creation, modification and removal of [Collider]'s based on the
 //! `PhysicsCollider` `Component`. This `System` depends on
-//! `SyncBodiesToPhysicsSystem` as [Collider] can depend on [RigidBody].
+//! `SyncBodiesToPhysicsSystem` as [Collider] can depend on [RigidBody] and on
+//! the `HierarchySystem` to find the [RigidBody] of parent `Entity`s.
 //!
 //! 3. `specs_physics::systems::SyncParametersToPhysicsSystem` - handles the
 //! modification of the [nphysics] `World`s parameters.
 //!
-//! 4. `specs_physics::systems::PhysicsStepperSystem` - handles the progression
+//! 4. `specs_physics::systems::SyncGravityFieldsToPhysicsSystem` - handles
+//! the `specs_physics::gravity::GravityField` `Component`s, which are applied
+//! by a force generator during the step. This `System` depends on
+//! `SyncBodiesToPhysicsSystem` and `SyncParametersToPhysicsSystem`.
+//!
+//! 5. `specs_physics::systems::SyncFluidVolumesToPhysicsSystem` - handles the
+//! buoyancy and drag of bodies within `specs_physics::fluids::FluidVolume`s,
+//! which are applied by a force generator during the step. This `System`
+//! depends on `SyncCollidersToPhysicsSystem` and
+//! `SyncParametersToPhysicsSystem`.
+//!
+//! 6. `specs_physics::systems::ExplosionSystem` - handles the `Explosion`s
+//! queued in the `specs_physics::explosions::Explosions` resource. This
+//! `System` depends on `SyncBodiesToPhysicsSystem` and
+//! `SyncCollidersToPhysicsSystem`.
+//!
+//! 7. `specs_physics::systems::SyncPairFilterToPhysicsSystem` - updates a
+//! `specs_physics::filters::PairFilter`, e.g. the `IgnoredCollisions` applying
+//! `IgnoreCollisionsWith` `Component`s, for the next step.
+//!
+//! 8. `specs_physics::systems::PhysicsStepperSystem` - handles the progression
 //! of the [nphysics] `World` and causes objects to actually move and
 //! change their position. This `System` is the backbone for collision
-//! detection.
+//! detection. The `specs_physics::parameters::PhysicsControl` resource pauses,
+//! single-steps or time scales the simulation.
 //!
-//! 5. `specs_physics::systems::SyncBodiesFromPhysicsSystem` -
+//! 9. `specs_physics::systems::SyncBodiesFromPhysicsSystem` -
 //! handles the synchronisation of [RigidBody] positions and dynamics back
 //! into the [Specs] `Component`s. This `System` also utilises the
 //! `Pose` *trait* implementation.
@@ -162,19 +253,31 @@
 //!
 //! ```rust,no_run
 //! use specs::DispatcherBuilder;
+//! use specs_hierarchy::HierarchySystem;
 //! use specs_physics::{
+//!     filters::IgnoredCollisions,
 //!     systems::{
+//!         ExplosionSystem,
 //!         PhysicsStepperSystem,
 //!         SyncBodiesFromPhysicsSystem,
 //!         SyncBodiesToPhysicsSystem,
 //!         SyncCollidersToPhysicsSystem,
+//!         SyncFluidVolumesToPhysicsSystem,
+//!         SyncGravityFieldsToPhysicsSystem,
+//!         SyncPairFilterToPhysicsSystem,
 //!         SyncParametersToPhysicsSystem,
 //!     },
+//!     PhysicsParent,
 //!     SimplePosition,
 //! };
 //!
 //! let dispatcher = DispatcherBuilder::new()
 //!     .with(
+//!         HierarchySystem::<PhysicsParent>::new(),
+//!         "physics_hierarchy_system",
+//!         &[],
+//!     )
+//!     .with(
 //!         SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
 //!         "sync_bodies_to_physics_system",
 //!         &[],
@@ -182,7 +285,7 @@
 //!     .with(
 //!         SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
 //!         "sync_colliders_to_physics_system",
-//!         &["sync_bodies_to_physics_system"],
+//!         &["sync_bodies_to_physics_system", "physics_hierarchy_system"],
 //!     )
 //!     .with(
 //!         SyncParametersToPhysicsSystem::<f32>::default(),
@@ -190,12 +293,45 @@
 //!         &[],
 //!     )
 //!     .with(
+//!         SyncGravityFieldsToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+//!         "sync_gravity_fields_to_physics_system",
+//!         &[
+//!             "sync_bodies_to_physics_system",
+//!             "sync_gravity_to_physics_system",
+//!         ],
+//!     )
+//!     .with(
+//!         SyncFluidVolumesToPhysicsSystem::<f32>::default(),
+//!         "sync_fluid_volumes_to_physics_system",
+//!         &[
+//!             "sync_colliders_to_physics_system",
+//!             "sync_gravity_to_physics_system",
+//!         ],
+//!     )
+//!     .with(
+//!         ExplosionSystem::<f32>::default(),
+//!         "explosion_system",
+//!         &[
+//!             "sync_bodies_to_physics_system",
+//!             "sync_colliders_to_physics_system",
+//!         ],
+//!     )
+//!     .with(
+//!         SyncPairFilterToPhysicsSystem::<f32, IgnoredCollisions>::default(),
+//!         "sync_ignored_collisions_to_physics_system",
+//!         &[],
+//!     )
+//!     .with(
 //!         PhysicsStepperSystem::<f32>::default(),
 //!         "physics_stepper_system",
 //!         &[
 //!             "sync_bodies_to_physics_system",
 //!             "sync_colliders_to_physics_system",
 //!             "sync_gravity_to_physics_system",
+//!             "sync_gravity_fields_to_physics_system",
+//!             "sync_fluid_volumes_to_physics_system",
+//!             "explosion_system",
+//!             "sync_ignored_collisions_to_physics_system",
 //!         ],
 //!     )
 //!     .with(
@@ -244,35 +380,61 @@ pub extern crate nphysics2d as nphysics;
 pub extern crate nphysics3d as nphysics;
 pub use shrev;
 
-pub use bodies::{PhysicsBody, PhysicsBodyBuilder};
-pub use colliders::{PhysicsCollider, PhysicsColliderBuilder};
+pub use bodies::{PhysicsBody, PhysicsBodyBuilder, PhysicsTimeScale};
+pub use colliders::{PhysicsCollider, PhysicsColliderBuilder, PhysicsColliders};
+pub use error::PhysicsError;
 pub use nalgebra as math;
 #[allow(deprecated)]
 pub use pose::{Pose, Position, SimplePosition};
 
-use std::collections::HashMap;
+use std::{collections::{HashMap, HashSet},
+          sync::{Arc, Mutex, MutexGuard}};
 
 use specs::{world::Index, Component, DenseVecStorage, Dispatcher, DispatcherBuilder, Entity,
             FlaggedStorage, Write};
-use specs_hierarchy::Parent;
+use specs_hierarchy::{Hierarchy, HierarchySystem, Parent};
 
+use ncollide::pipeline::{DefaultContactDispatcher, DefaultProximityDispatcher, NarrowPhase};
 use nphysics::math::Vector;
 use nphysics::object::{DefaultBodyHandle, DefaultColliderHandle};
-use nphysics::{counters::Counters, material::MaterialsCoefficientsTable,
-               solver::IntegrationParameters};
+use nphysics::{counters::Counters,
+               material::MaterialsCoefficientsTable,
+               solver::{IntegrationParameters, SignoriniCoulombPyramidModel}};
 
 use nphysics::world::{DefaultGeometricalWorld, DefaultMechanicalWorld};
 
+use contacts::{ContactModifier, ContactModifiers, ModifiedContactModel};
+use filters::{FilteredBroadPhase, IgnoredCollisions, PairFilters};
 use nalgebra::RealField;
-use systems::{PhysicsStepperSystem, SyncBodiesFromPhysicsSystem, SyncBodiesToPhysicsSystem,
-              SyncCollidersToPhysicsSystem, SyncParametersToPhysicsSystem};
+use systems::{ExplosionSystem, PhysicsStepperSystem, SyncBodiesFromPhysicsSystem,
+              SyncBodiesToPhysicsSystem, SyncCollidersToPhysicsSystem,
+              SyncFluidVolumesToPhysicsSystem, SyncGravityFieldsToPhysicsSystem,
+              SyncPairFilterToPhysicsSystem, SyncParametersToPhysicsSystem};
 
 pub mod bodies;
 pub mod colliders;
+pub mod contacts;
+#[cfg(feature = "physics3d")]
+pub mod decomposition;
+pub mod error;
 pub mod events;
+pub mod explosions;
+pub mod filters;
+pub mod fluids;
+pub mod gravity;
+pub mod heightfield;
+#[cfg(any(feature = "physics3d", feature = "png"))]
+mod image;
+pub mod layers;
+pub mod materials;
+pub mod mesh;
+#[cfg(feature = "physics2d")]
+pub mod outline;
 pub mod parameters;
 pub mod pose;
 pub mod systems;
+#[cfg(feature = "physics2d")]
+pub mod tilemap;
 
 /// Resource holding the internal fields where physics computation occurs.
 /// Some inspection methods are exposed to allow debugging.
@@ -287,6 +449,17 @@ pub struct Physics<N: RealField> {
     /// Hashmap of Entities to internal Collider handles.
     /// Necessary for reacting to removed Components.
     pub(crate) collider_handles: HashMap<Index, DefaultColliderHandle>,
+    /// Hashmap of Entities to the internal Collider handles created from their
+    /// `PhysicsColliders`. Necessary for reacting to removed Components.
+    pub(crate) collider_list_handles: HashMap<Index, Vec<DefaultColliderHandle>>,
+    /// The `PairFilter`s registered by `SyncPairFilterToPhysicsSystem`s, shared
+    /// with the broad phase of the `geometric_world`.
+    pub(crate) pair_filters: Arc<Mutex<PairFilters>>,
+    /// Handles of the bodies whose `PhysicsBody` has `ccd_enabled` set.
+    pub(crate) ccd_bodies: HashSet<DefaultBodyHandle>,
+    /// The `ContactModifier`s called by the contact model of the
+    /// `mechanical_world`, which is only installed while any are registered.
+    pub(crate) contact_modifiers: ContactModifiers<N>,
 }
 
 // Some non-mutating methods for diagnostics and testing
@@ -322,19 +495,84 @@ impl<N: RealField> Physics<N> {
     }
 
     /// Retrieves the internal lookup table for friction and restitution
-    /// constants. Exposing this for modification is TODO.
+    /// constants. See also `PhysicsMaterials` for setting these constants.
     pub fn materials_coefficients_table(&self) -> &MaterialsCoefficientsTable<N> {
         &self.mechanical_world.material_coefficients
     }
+
+    /// Registers a `ContactModifier` which is called for every pair of
+    /// colliders in contact, after the ones registered before it.
+    ///
+    /// Modifiers are run by a contact model `Physics` installs into the
+    /// `mechanical_world`'s solver when the first one is registered; they have
+    /// no effect if it is replaced afterwards.
+    pub fn add_contact_modifier<M: ContactModifier<N>>(&mut self, modifier: M) {
+        if self.lock_contact_modifiers().is_empty() {
+            self.mechanical_world
+                .solver
+                .set_contact_model(Box::new(ModifiedContactModel::new(
+                    self.contact_modifiers.clone(),
+                )));
+        }
+        self.lock_contact_modifiers().push(Box::new(modifier));
+    }
+
+    /// Removes all registered `ContactModifier`s and restores the stock
+    /// contact model of nphysics.
+    pub fn clear_contact_modifiers(&mut self) {
+        self.lock_contact_modifiers().clear();
+        self.mechanical_world
+            .solver
+            .set_contact_model(Box::new(SignoriniCoulombPyramidModel::new()));
+    }
+
+    /// Returns the handle of the [Collider] created for the entity with the
+    /// given `Index`, or for its `PhysicsColliders` entry `part`.
+    pub(crate) fn collider_handle(
+        &self,
+        id: Index,
+        part: Option<usize>,
+    ) -> Option<DefaultColliderHandle> {
+        match part {
+            Some(part) => self
+                .collider_list_handles
+                .get(&id)
+                .and_then(|handles| handles.get(part))
+                .cloned(),
+            None => self.collider_handles.get(&id).cloned(),
+        }
+    }
+
+    fn lock_contact_modifiers(&self) -> MutexGuard<'_, Vec<Box<dyn ContactModifier<N>>>> {
+        match self.contact_modifiers.lock() {
+            Ok(modifiers) => modifiers,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
 }
 
 impl<N: RealField> Default for Physics<N> {
     fn default() -> Self {
+        // the GeometricalWorld of nphysics ignores its pair filters, so they are
+        // applied by a broad phase of our own instead
+        let pair_filters = Arc::new(Mutex::new(PairFilters::default()));
+        let geometric_world = DefaultGeometricalWorld::from_parts(
+            FilteredBroadPhase::new(pair_filters.clone()),
+            NarrowPhase::new(
+                Box::new(DefaultContactDispatcher::new()),
+                Box::new(DefaultProximityDispatcher::new()),
+            ),
+        );
+
         Self {
             mechanical_world: DefaultMechanicalWorld::new(Vector::zeros()),
-            geometric_world: DefaultGeometricalWorld::new(),
+            geometric_world,
             body_handles: HashMap::new(),
             collider_handles: HashMap::new(),
+            collider_list_handles: HashMap::new(),
+            pair_filters,
+            ccd_bodies: HashSet::new(),
+            contact_modifiers: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -359,6 +597,12 @@ impl Parent for PhysicsParent {
     }
 }
 
+/// The `PhysicsHierarchy` resource keeps track of the parent/child
+/// relationships defined by `PhysicsParent` `Component`s. It is maintained by
+/// a `HierarchySystem<PhysicsParent>` and used to attach colliders to the body
+/// of their nearest ancestor.
+pub type PhysicsHierarchy = Hierarchy<PhysicsParent>;
+
 /// Convenience function for configuring and building a `Dispatcher` with all
 /// required physics related `System`s.
 ///
@@ -386,7 +630,15 @@ where
     N: RealField,
     P: Pose<N>,
 {
-    // add SyncBodiesToPhysicsSystem first since we have to start with bodies;
+    // add the HierarchySystem for PhysicsParent first; it maintains the
+    // PhysicsHierarchy used to find the body a collider is attached to
+    dispatcher_builder.add(
+        HierarchySystem::<PhysicsParent>::new(),
+        "physics_hierarchy_system",
+        &[],
+    );
+
+    // add SyncBodiesToPhysicsSystem next since we have to start with bodies;
     // colliders can exist without a body but in most cases have a body parent
     dispatcher_builder.add(
         SyncBodiesToPhysicsSystem::<N, P>::default(),
@@ -394,12 +646,12 @@ where
         &[],
     );
 
-    // add SyncCollidersToPhysicsSystem next with SyncBodiesToPhysicsSystem as its
-    // dependency
+    // add SyncCollidersToPhysicsSystem next with SyncBodiesToPhysicsSystem and
+    // the HierarchySystem as its dependencies
     dispatcher_builder.add(
         SyncCollidersToPhysicsSystem::<N, P>::default(),
         "sync_colliders_to_physics_system",
-        &["sync_bodies_to_physics_system"],
+        &["sync_bodies_to_physics_system", "physics_hierarchy_system"],
     );
 
     // add SyncParametersToPhysicsSystem; this System can be added at any point in
@@ -411,6 +663,47 @@ where
         &[],
     );
 
+    // add SyncGravityFieldsToPhysicsSystem; it depends on the body handles and the
+    // global gravity being up to date
+    dispatcher_builder.add(
+        SyncGravityFieldsToPhysicsSystem::<N, P>::default(),
+        "sync_gravity_fields_to_physics_system",
+        &[
+            "sync_bodies_to_physics_system",
+            "sync_parameters_to_physics_system",
+        ],
+    );
+
+    // add SyncFluidVolumesToPhysicsSystem; it depends on the collider handles and
+    // the global gravity being up to date
+    dispatcher_builder.add(
+        SyncFluidVolumesToPhysicsSystem::<N>::default(),
+        "sync_fluid_volumes_to_physics_system",
+        &[
+            "sync_colliders_to_physics_system",
+            "sync_parameters_to_physics_system",
+        ],
+    );
+
+    // add ExplosionSystem; it queries the colliders and applies impulses to the
+    // bodies created by the previous Systems
+    dispatcher_builder.add(
+        ExplosionSystem::<N>::default(),
+        "explosion_system",
+        &[
+            "sync_bodies_to_physics_system",
+            "sync_colliders_to_physics_system",
+        ],
+    );
+
+    // add the SyncPairFilterToPhysicsSystem for IgnoreCollisionsWith Components;
+    // pair filters only have to be updated before the nphysics World is stepped
+    dispatcher_builder.add(
+        SyncPairFilterToPhysicsSystem::<N, IgnoredCollisions>::default(),
+        "sync_ignored_collisions_to_physics_system",
+        &[],
+    );
+
     // add PhysicsStepperSystem after all other Systems that write data to the
     // nphysics World and has to depend on them; this System is used to progress the
     // nphysics World for all existing objects
@@ -421,6 +714,10 @@ where
             "sync_bodies_to_physics_system",
             "sync_colliders_to_physics_system",
             "sync_parameters_to_physics_system",
+            "sync_gravity_fields_to_physics_system",
+            "sync_fluid_volumes_to_physics_system",
+            "explosion_system",
+            "sync_ignored_collisions_to_physics_system",
         ],
     );
 
@@ -433,3 +730,22 @@ where
         &["physics_stepper_system"],
     );
 }
+
+/// Creates a `World` holding the nphysics sets and a `Dispatcher` with all
+/// physics `System`s, both already set up for the given `Pose`.
+#[cfg(all(test, feature = "physics3d"))]
+pub(crate) fn test_world<P: Pose<f32>>() -> (specs::World, Dispatcher<'static, 'static>) {
+    use nphysics::{force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{DefaultBodySet, DefaultColliderSet}};
+
+    let mut world = specs::World::new();
+    let mut dispatcher = physics_dispatcher::<f32, P>();
+    world.add_resource(DefaultBodySet::<f32>::new());
+    world.add_resource(DefaultColliderSet::<f32>::new());
+    world.add_resource(DefaultJointConstraintSet::<f32>::new());
+    world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+    dispatcher.setup(&mut world.res);
+
+    (world, dispatcher)
+}
@@ -0,0 +1,306 @@
+//! # Materials module
+//! A library of named materials, so colliders can share friction and
+//! restitution settings and pairs of materials can be given their own
+//! coefficients.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{materials::{PhysicsMaterial, PhysicsMaterials},
+//!                     PhysicsColliderBuilder};
+//!
+//! let mut materials = PhysicsMaterials::<f32>::default();
+//! materials.add_material("ice", PhysicsMaterial {
+//!     friction: 0.02,
+//!     ..PhysicsMaterial::default()
+//! });
+//! materials.add_material("rubber", PhysicsMaterial {
+//!     friction: 1.0,
+//!     restitution: 0.8,
+//!     ..PhysicsMaterial::default()
+//! });
+//! // rubber grips a little better on ice than either material suggests
+//! materials.set_friction("ice", "rubber", Some(0.1))?;
+//! world.add_resource(materials);
+//!
+//! let physics_collider = PhysicsColliderBuilder::<f32>::from(shape)
+//!     .material_named("ice")
+//!     .build();
+//! ```
+
+use std::{collections::HashMap, mem};
+
+use crate::error::PhysicsError;
+use nalgebra::{convert, RealField};
+use nphysics::material::{BasicMaterial, MaterialCombineMode, MaterialHandle, MaterialId,
+                         MaterialsCoefficientsTable};
+use nphysics::math::Vector;
+
+/// The `PhysicsMaterial` defines the surface properties of colliders
+/// referring to it by name.
+#[derive(Clone, Copy, Debug)]
+pub struct PhysicsMaterial<N: RealField> {
+    /// Friction coefficient of the surface.
+    pub friction: N,
+    /// Restitution coefficient of the surface.
+    pub restitution: N,
+    /// The way friction coefficients are combined if no coefficient was set
+    /// for the pair of materials in contact.
+    pub friction_combine_mode: MaterialCombineMode,
+    /// The way restitution coefficients are combined if no coefficient was
+    /// set for the pair of materials in contact.
+    pub restitution_combine_mode: MaterialCombineMode,
+    /// The fictitious velocity at the surface, relative to the collider.
+    pub surface_velocity: Option<Vector<N>>,
+}
+
+impl<N: RealField> PhysicsMaterial<N> {
+    fn basic_material(&self, id: MaterialId) -> BasicMaterial<N> {
+        let mut material = BasicMaterial::new(self.restitution, self.friction);
+        material.id = Some(id);
+        material.friction_combine_mode = self.friction_combine_mode;
+        material.restitution_combine_mode = self.restitution_combine_mode;
+        material.surface_velocity = self.surface_velocity;
+        material
+    }
+}
+
+impl<N: RealField> Default for PhysicsMaterial<N> {
+    /// Matches the nphysics `BasicMaterial` default.
+    fn default() -> Self {
+        Self {
+            friction: convert(0.5),
+            restitution: N::zero(),
+            friction_combine_mode: MaterialCombineMode::Average,
+            restitution_combine_mode: MaterialCombineMode::Average,
+            surface_velocity: None,
+        }
+    }
+}
+
+/// The `PhysicsMaterials` resource registers `PhysicsMaterial`s by name.
+/// Colliders refer to a material through `PhysicsCollider::material_name`,
+/// which takes precedence over `PhysicsCollider::material`.
+///
+/// The `SyncCollidersToPhysicsSystem` syncs the coefficients of material pairs
+/// into the `MaterialsCoefficientsTable` of `Physics`, replacing coefficients
+/// set on the table directly, and recreates every collider using a definition
+/// that was replaced, as nphysics can't change the material of a `Collider`.
+#[derive(Clone, Debug)]
+pub struct PhysicsMaterials<N: RealField> {
+    names: Vec<String>,
+    materials: Vec<PhysicsMaterial<N>>,
+    friction: HashMap<(MaterialId, MaterialId), N>,
+    restitution: HashMap<(MaterialId, MaterialId), N>,
+    modified_materials: Vec<MaterialId>,
+    modified_coefficients: bool,
+}
+
+impl<N: RealField> PhysicsMaterials<N> {
+    /// Registers a material under the given name and returns its
+    /// `MaterialId`. Adding an already registered material replaces its
+    /// definition and keeps its `MaterialId`.
+    pub fn add_material(&mut self, name: &str, material: PhysicsMaterial<N>) -> MaterialId {
+        if let Some(id) = self.material_id(name) {
+            self.materials[id as usize] = material;
+            self.modified_materials.push(id);
+            return id;
+        }
+
+        self.names.push(name.to_string());
+        self.materials.push(material);
+        (self.names.len() - 1) as MaterialId
+    }
+
+    /// Returns the `MaterialId` of the material registered under the given
+    /// name.
+    pub fn material_id(&self, name: &str) -> Option<MaterialId> {
+        self.names
+            .iter()
+            .position(|material| material == name)
+            .map(|id| id as MaterialId)
+    }
+
+    /// Returns the material registered under the given name.
+    pub fn material(&self, name: &str) -> Option<&PhysicsMaterial<N>> {
+        self.material_id(name)
+            .map(|id| &self.materials[id as usize])
+    }
+
+    /// Returns the names of all registered materials, ordered by their
+    /// `MaterialId`.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the friction coefficient used between the two materials instead
+    /// of combining their own ones.
+    pub fn friction(&self, a: &str, b: &str) -> Result<Option<N>, PhysicsError> {
+        let key = self.pair(a, b)?;
+        Ok(self.friction.get(&key).cloned())
+    }
+
+    /// Sets the friction coefficient used between the two materials instead of
+    /// combining their own ones; `None` removes it.
+    pub fn set_friction(
+        &mut self,
+        a: &str,
+        b: &str,
+        friction: Option<N>,
+    ) -> Result<(), PhysicsError> {
+        let key = self.pair(a, b)?;
+        set_coefficient(&mut self.friction, key, friction);
+        self.modified_coefficients = true;
+
+        Ok(())
+    }
+
+    /// Returns the restitution coefficient used between the two materials
+    /// instead of combining their own ones.
+    pub fn restitution(&self, a: &str, b: &str) -> Result<Option<N>, PhysicsError> {
+        let key = self.pair(a, b)?;
+        Ok(self.restitution.get(&key).cloned())
+    }
+
+    /// Sets the restitution coefficient used between the two materials instead
+    /// of combining their own ones; `None` removes it.
+    pub fn set_restitution(
+        &mut self,
+        a: &str,
+        b: &str,
+        restitution: Option<N>,
+    ) -> Result<(), PhysicsError> {
+        let key = self.pair(a, b)?;
+        set_coefficient(&mut self.restitution, key, restitution);
+        self.modified_coefficients = true;
+
+        Ok(())
+    }
+
+    /// Returns a new `MaterialHandle` for the material registered under the
+    /// given name.
+    pub(crate) fn handle(&self, name: &str) -> Result<MaterialHandle<N>, PhysicsError> {
+        Ok(MaterialHandle::new(self.basic_material(name)?))
+    }
+
+    /// Returns the `BasicMaterial` colliders using the material registered
+    /// under the given name are built with.
+    pub(crate) fn basic_material(&self, name: &str) -> Result<BasicMaterial<N>, PhysicsError> {
+        let id = self.index(name)?;
+        Ok(self.materials[id as usize].basic_material(id))
+    }
+
+    /// Builds the nphysics lookup table from the coefficients of all pairs.
+    pub(crate) fn coefficients_table(&self) -> MaterialsCoefficientsTable<N> {
+        let mut table = MaterialsCoefficientsTable::new();
+        for ((a, b), friction) in &self.friction {
+            table.set_friction_coefficient(*a, *b, *friction);
+        }
+        for ((a, b), restitution) in &self.restitution {
+            table.set_restitution_coefficient(*a, *b, *restitution);
+        }
+        table
+    }
+
+    /// Returns the names of the materials whose definition was replaced since
+    /// the last call.
+    pub(crate) fn take_modified_materials(&mut self) -> Vec<String> {
+        let mut modified = mem::take(&mut self.modified_materials);
+        modified.sort();
+        modified.dedup();
+        modified
+            .into_iter()
+            .map(|id| self.names[id as usize].clone())
+            .collect()
+    }
+
+    /// Returns `true` if the coefficients of pairs of materials changed since
+    /// the last call.
+    pub(crate) fn take_modified_coefficients(&mut self) -> bool {
+        let modified = self.modified_coefficients;
+        self.modified_coefficients = false;
+        modified
+    }
+
+    fn index(&self, name: &str) -> Result<MaterialId, PhysicsError> {
+        self.material_id(name)
+            .ok_or_else(|| PhysicsError::UnknownMaterial {
+                name: name.to_string(),
+            })
+    }
+
+    fn pair(&self, a: &str, b: &str) -> Result<(MaterialId, MaterialId), PhysicsError> {
+        let (a, b) = (self.index(a)?, self.index(b)?);
+        Ok((a.min(b), a.max(b)))
+    }
+}
+
+impl<N: RealField> Default for PhysicsMaterials<N> {
+    fn default() -> Self {
+        Self {
+            names: Vec::new(),
+            materials: Vec::new(),
+            friction: HashMap::new(),
+            restitution: HashMap::new(),
+            modified_materials: Vec::new(),
+            modified_coefficients: false,
+        }
+    }
+}
+
+fn set_coefficient<N: RealField>(
+    coefficients: &mut HashMap<(MaterialId, MaterialId), N>,
+    key: (MaterialId, MaterialId),
+    coefficient: Option<N>,
+) {
+    match coefficient {
+        Some(coefficient) => {
+            coefficients.insert(key, coefficient);
+        }
+        None => {
+            coefficients.remove(&key);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use super::{PhysicsMaterial, PhysicsMaterials};
+
+    #[test]
+    fn material_library() {
+        let mut materials = PhysicsMaterials::<f32>::default();
+        let ice = materials.add_material(
+            "ice",
+            PhysicsMaterial {
+                friction: 0.02,
+                ..PhysicsMaterial::default()
+            },
+        );
+        let rubber = materials.add_material("rubber", PhysicsMaterial::default());
+        assert_ne!(ice, rubber);
+        assert_eq!(materials.take_modified_materials().len(), 0);
+
+        // replacing a definition keeps the id and reports the material
+        let replaced = materials.add_material("rubber", PhysicsMaterial::default());
+        assert_eq!(replaced, rubber);
+        assert_eq!(
+            materials.take_modified_materials(),
+            vec!["rubber".to_string()]
+        );
+
+        materials.set_friction("rubber", "ice", Some(0.1)).unwrap();
+        assert_eq!(materials.friction("ice", "rubber"), Ok(Some(0.1)));
+        assert!(materials.take_modified_coefficients());
+        let table = materials.coefficients_table();
+        assert_eq!(table.friction_coefficient(ice, rubber), Some(0.1));
+        assert_eq!(table.restitution_coefficient(ice, rubber), None);
+
+        materials.set_friction("ice", "rubber", None).unwrap();
+        let table = materials.coefficients_table();
+        assert_eq!(table.friction_coefficient(ice, rubber), None);
+
+        assert!(materials.set_friction("ice", "wood", Some(0.3)).is_err());
+        assert_eq!(materials.basic_material("ice").unwrap().id, Some(ice));
+    }
+}
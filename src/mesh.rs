@@ -0,0 +1,559 @@
+//! # Mesh module
+//! Loaders for Wavefront OBJ and binary STL files, turning them into
+//! `MeshData` that can be used for `Shape::TriMesh` and `Shape::ConvexHull`
+//! definitions.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::mesh::{Mesh, MeshLoadOptions, UpAxis};
+//!
+//! let options = MeshLoadOptions {
+//!     up_axis: UpAxis::Z,
+//!     weld_distance: Some(0.0001),
+//!     ..MeshLoadOptions::default()
+//! };
+//! let level = Mesh::merge("level", Mesh::load_obj("assets/level.obj", &options)?);
+//! let shape = level.into_trimesh_shape();
+//! ```
+
+use std::{cmp::Ordering, collections::HashMap, fs, mem, path::Path};
+
+use nalgebra::{convert, distance, Point2, Point3, RealField, Vector3};
+
+use crate::{colliders::{IntoMesh, MeshData},
+            error::PhysicsError};
+
+#[cfg(feature = "physics3d")]
+use crate::colliders::Shape;
+
+/// The axis pointing upwards in a mesh file. Meshes are converted into the
+/// Y-up coordinate system while loading.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UpAxis {
+    Y,
+    Z,
+}
+
+/// `MeshLoadOptions` describe the conversions applied to the vertices of a
+/// mesh file while loading it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshLoadOptions<N: RealField> {
+    /// Scale applied to every vertex before the axis conversion.
+    ///
+    /// default: `(1.0, 1.0, 1.0)`
+    pub scale: Vector3<N>,
+
+    /// The axis pointing upwards in the mesh file.
+    ///
+    /// default: `UpAxis::Y`
+    pub up_axis: UpAxis,
+
+    /// Vertices closer to each other than this distance are merged into one,
+    /// triangles collapsing by doing so are removed.
+    ///
+    /// default: `None`
+    pub weld_distance: Option<N>,
+}
+
+impl<N: RealField> Default for MeshLoadOptions<N> {
+    fn default() -> Self {
+        Self {
+            scale: Vector3::repeat(N::one()),
+            up_axis: UpAxis::Y,
+            weld_distance: None,
+        }
+    }
+}
+
+impl<N: RealField> MeshLoadOptions<N> {
+    fn transform(&self, point: Point3<N>) -> Point3<N> {
+        let point = Point3::from(point.coords.component_mul(&self.scale));
+
+        match self.up_axis {
+            UpAxis::Y => point,
+            UpAxis::Z => Point3::new(point.x, point.z, -point.y),
+        }
+    }
+
+    /// Negative scales mirror the mesh, which flips the winding order of its
+    /// triangles.
+    fn flips_winding(&self) -> bool {
+        self.scale.x * self.scale.y * self.scale.z < N::zero()
+    }
+}
+
+/// A `Mesh` is a named part of a mesh file, e.g. an object or group of an OBJ
+/// file, and implements `IntoMesh` so it can be used for `Shape::TriMesh`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh<N: RealField> {
+    pub name: String,
+    pub data: MeshData<N>,
+}
+
+impl<N: RealField> IntoMesh for Mesh<N> {
+    type N = N;
+
+    fn points(&self) -> MeshData<Self::N> {
+        self.data.clone()
+    }
+}
+
+impl<N: RealField> Mesh<N> {
+    /// Loads all objects and groups of the Wavefront OBJ file at the given
+    /// path as separate `Mesh`es.
+    pub fn load_obj<P: AsRef<Path>>(
+        path: P,
+        options: &MeshLoadOptions<N>,
+    ) -> Result<Vec<Self>, PhysicsError> {
+        Self::obj_from_bytes(&fs::read(path)?, options)
+    }
+
+    /// Parses all objects and groups of a Wavefront OBJ file as separate
+    /// `Mesh`es. Polygons are triangulated and texture coordinates are kept,
+    /// everything else besides vertices and faces is ignored.
+    pub fn obj_from_bytes(
+        bytes: &[u8],
+        options: &MeshLoadOptions<N>,
+    ) -> Result<Vec<Self>, PhysicsError> {
+        let source = String::from_utf8_lossy(bytes);
+
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut meshes = Vec::new();
+        let mut builder = ObjMeshBuilder::new("default".to_string());
+
+        for (number, text) in source.lines().enumerate() {
+            let line = number + 1;
+            let mut tokens = text.split_whitespace();
+
+            match tokens.next() {
+                Some("v") => {
+                    let coords = parse_coords::<N>(&mut tokens, 3, line)?;
+                    positions.push(options.transform(Point3::new(coords[0], coords[1], coords[2])));
+                }
+                Some("vt") => {
+                    let coords = parse_coords::<N>(&mut tokens, 2, line)?;
+                    uvs.push(Point2::new(coords[0], coords[1]));
+                }
+                Some("f") => {
+                    let corners = tokens
+                        .map(|token| parse_corner(token, positions.len(), uvs.len(), line))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if corners.len() < 3 {
+                        return Err(invalid_obj(line, "faces need at least three vertices"));
+                    }
+
+                    // triangulate polygons as a fan around their first vertex
+                    for i in 1..corners.len() - 1 {
+                        let mut triangle = [corners[0], corners[i], corners[i + 1]];
+                        if options.flips_winding() {
+                            triangle.swap(1, 2);
+                        }
+                        builder.push_triangle(triangle, &positions, &uvs);
+                    }
+                }
+                Some("o") | Some("g") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    let previous = mem::replace(&mut builder, ObjMeshBuilder::new(name));
+                    meshes.extend(previous.build(options));
+                }
+                _ => {}
+            }
+        }
+        meshes.extend(builder.build(options));
+
+        Ok(meshes)
+    }
+
+    /// Loads the binary STL file at the given path.
+    pub fn load_stl<P: AsRef<Path>>(
+        path: P,
+        options: &MeshLoadOptions<N>,
+    ) -> Result<Self, PhysicsError> {
+        let name = path
+            .as_ref()
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let mut mesh = Self::stl_from_bytes(&fs::read(path)?, options)?;
+        mesh.name = name;
+
+        Ok(mesh)
+    }
+
+    /// Parses a binary STL file. As STL stores every triangle on its own,
+    /// enabling `MeshLoadOptions::weld_distance` is recommended.
+    pub fn stl_from_bytes(
+        bytes: &[u8],
+        options: &MeshLoadOptions<N>,
+    ) -> Result<Self, PhysicsError> {
+        // 80 byte header followed by the amount of triangles
+        if bytes.len() < 84 {
+            return Err(invalid_stl("file is too short"));
+        }
+        let count = read_u32(&bytes[80..84]) as usize;
+        if bytes.len() != 84 + count * 50 {
+            return Err(invalid_stl(
+                "size doesn't match the triangle count; ASCII STL is not supported",
+            ));
+        }
+
+        let mut points = Vec::with_capacity(count * 3);
+        let mut indices = Vec::with_capacity(count);
+        for triangle in bytes[84..].chunks(50) {
+            // every triangle starts with its normal, which is skipped
+            for vertex in triangle[12..48].chunks(12) {
+                let coords = vertex
+                    .chunks(4)
+                    .map(|x| convert::<f64, N>(f64::from(f32::from_bits(read_u32(x)))))
+                    .collect::<Vec<_>>();
+                if !coords.iter().all(|x| x.is_finite()) {
+                    return Err(PhysicsError::NonFinitePoint { shape: "STL" });
+                }
+                points.push(options.transform(Point3::new(coords[0], coords[1], coords[2])));
+            }
+
+            let first = points.len() - 3;
+            indices.push(match options.flips_winding() {
+                true => Point3::new(first, first + 2, first + 1),
+                false => Point3::new(first, first + 1, first + 2),
+            });
+        }
+
+        let mut mesh = Mesh {
+            name: String::new(),
+            data: (points, indices, None),
+        };
+        if let Some(weld_distance) = options.weld_distance {
+            mesh.weld(weld_distance);
+        }
+
+        Ok(mesh)
+    }
+
+    /// Merges several `Mesh`es into a single one. Texture coordinates are only
+    /// kept if all of them have some.
+    pub fn merge<I: IntoIterator<Item = Self>>(name: &str, meshes: I) -> Self {
+        let mut points = Vec::new();
+        let mut indices = Vec::new();
+        let mut uvs = Some(Vec::new());
+
+        for mesh in meshes {
+            let (mesh_points, mesh_indices, mesh_uvs) = mesh.data;
+            let offset = points.len();
+
+            indices.extend(
+                mesh_indices
+                    .into_iter()
+                    .map(|x| Point3::new(x[0] + offset, x[1] + offset, x[2] + offset)),
+            );
+            points.extend(mesh_points);
+            uvs = match (uvs, mesh_uvs) {
+                (Some(mut uvs), Some(mesh_uvs)) => {
+                    uvs.extend(mesh_uvs);
+                    Some(uvs)
+                }
+                _ => None,
+            };
+        }
+
+        Mesh {
+            name: name.to_string(),
+            data: (points, indices, uvs),
+        }
+    }
+
+    /// Merges all vertices closer to each other than `max_distance` and removes
+    /// the triangles collapsing by doing so. Texture coordinates of merged
+    /// vertices are taken from the first one.
+    pub fn weld(&mut self, max_distance: N) {
+        let (points, indices, uvs) = &mut self.data;
+
+        // sort by the x coordinate, so only a small window of vertices has to
+        // be checked for each vertex
+        let mut order = (0..points.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| {
+            points[*a]
+                .x
+                .partial_cmp(&points[*b].x)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let mut remap = vec![0; points.len()];
+        let mut welded_points = Vec::new();
+        let mut welded_uvs = Vec::new();
+        let mut window_start = 0;
+        for (i, current) in order.iter().enumerate() {
+            let point = &points[*current];
+            while points[order[window_start]].x < point.x - max_distance {
+                window_start += 1;
+            }
+
+            let existing = order[window_start..i]
+                .iter()
+                .find(|other| distance(&points[**other], point) <= max_distance);
+            remap[*current] = match existing {
+                Some(other) => remap[*other],
+                None => {
+                    welded_points.push(*point);
+                    if let Some(uvs) = uvs.as_ref() {
+                        welded_uvs.push(uvs[*current]);
+                    }
+                    welded_points.len() - 1
+                }
+            };
+        }
+
+        indices.retain(|triangle| {
+            let (a, b, c) = (remap[triangle.x], remap[triangle.y], remap[triangle.z]);
+            a != b && b != c && a != c
+        });
+        for triangle in indices.iter_mut() {
+            *triangle = Point3::new(remap[triangle.x], remap[triangle.y], remap[triangle.z]);
+        }
+        *points = welded_points;
+        if uvs.is_some() {
+            *uvs = Some(welded_uvs);
+        }
+    }
+
+    /// Turns this `Mesh` into a `Shape::TriMesh`.
+    #[cfg(feature = "physics3d")]
+    pub fn into_trimesh_shape(self) -> Shape<N> {
+        Shape::TriMesh {
+            handle: Box::new(self),
+        }
+    }
+
+    /// Turns this `Mesh` into a `Shape::ConvexHull` enclosing all of its
+    /// vertices.
+    #[cfg(feature = "physics3d")]
+    pub fn into_convex_hull_shape(self) -> Shape<N> {
+        Shape::ConvexHull {
+            points: self.data.0,
+        }
+    }
+}
+
+/// Collects the triangles of a single OBJ object or group. OBJ indices refer to
+/// the vertices of the whole file, so they are mapped to vertices local to the
+/// `Mesh`; vertices used with different texture coordinates are split up.
+struct ObjMeshBuilder<N: RealField> {
+    name: String,
+    vertices: HashMap<(usize, Option<usize>), usize>,
+    points: Vec<Point3<N>>,
+    uvs: Vec<Point2<N>>,
+    has_uvs: bool,
+    indices: Vec<Point3<usize>>,
+}
+
+impl<N: RealField> ObjMeshBuilder<N> {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            vertices: HashMap::new(),
+            points: Vec::new(),
+            uvs: Vec::new(),
+            has_uvs: false,
+            indices: Vec::new(),
+        }
+    }
+
+    fn push_triangle(
+        &mut self,
+        triangle: [(usize, Option<usize>); 3],
+        positions: &[Point3<N>],
+        uvs: &[Point2<N>],
+    ) {
+        let mut local = [0; 3];
+        for (corner, vertex) in triangle.iter().enumerate() {
+            local[corner] = match self.vertices.get(vertex) {
+                Some(index) => *index,
+                None => {
+                    self.vertices.insert(*vertex, self.points.len());
+                    self.points.push(positions[vertex.0]);
+                    self.uvs
+                        .push(vertex.1.map_or_else(Point2::origin, |uv| uvs[uv]));
+                    self.has_uvs |= vertex.1.is_some();
+                    self.points.len() - 1
+                }
+            };
+        }
+        self.indices.push(Point3::new(local[0], local[1], local[2]));
+    }
+
+    fn build(self, options: &MeshLoadOptions<N>) -> Option<Mesh<N>> {
+        if self.indices.is_empty() {
+            return None;
+        }
+
+        let uvs = match self.has_uvs {
+            true => Some(self.uvs),
+            false => None,
+        };
+        let mut mesh = Mesh {
+            name: self.name,
+            data: (self.points, self.indices, uvs),
+        };
+        if let Some(weld_distance) = options.weld_distance {
+            mesh.weld(weld_distance);
+        }
+
+        Some(mesh)
+    }
+}
+
+fn parse_coords<'a, N: RealField>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    count: usize,
+    line: usize,
+) -> Result<Vec<N>, PhysicsError> {
+    let coords = tokens
+        .take(count)
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map(convert::<f64, N>)
+                .map_err(|_| invalid_obj(line, &format!("invalid number \"{}\"", token)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if coords.len() != count {
+        return Err(invalid_obj(line, "not enough coordinates"));
+    }
+    if !coords.iter().all(|x| x.is_finite()) {
+        return Err(PhysicsError::NonFinitePoint { shape: "OBJ" });
+    }
+
+    Ok(coords)
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into zero based
+/// vertex and texture coordinate indices.
+fn parse_corner(
+    token: &str,
+    positions: usize,
+    uvs: usize,
+    line: usize,
+) -> Result<(usize, Option<usize>), PhysicsError> {
+    let mut parts = token.split('/');
+    let position = parse_index(parts.next().unwrap_or_default(), positions, line)?;
+    let uv = match parts.next() {
+        Some(uv) if !uv.is_empty() => Some(parse_index(uv, uvs, line)?),
+        _ => None,
+    };
+
+    Ok((position, uv))
+}
+
+/// Resolves a one based OBJ index, which counts backwards from the last
+/// element if it is negative.
+fn parse_index(token: &str, len: usize, line: usize) -> Result<usize, PhysicsError> {
+    let index = token
+        .parse::<isize>()
+        .map_err(|_| invalid_obj(line, &format!("invalid index \"{}\"", token)))?;
+
+    let resolved = match index {
+        index if index > 0 => index as usize - 1,
+        index if index < 0 && (-index) as usize <= len => len - (-index) as usize,
+        _ => len,
+    };
+    match resolved < len {
+        true => Ok(resolved),
+        false => Err(PhysicsError::IndexOutOfBounds {
+            shape: "OBJ",
+            index: resolved,
+            len,
+        }),
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn invalid_obj(line: usize, reason: &str) -> PhysicsError {
+    PhysicsError::InvalidMesh {
+        format: "OBJ",
+        reason: format!("line {}: {}", line, reason),
+    }
+}
+
+fn invalid_stl(reason: &str) -> PhysicsError {
+    PhysicsError::InvalidMesh {
+        format: "STL",
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use super::{Mesh, MeshLoadOptions, UpAxis};
+    use nalgebra::{Point3, Vector3};
+
+    const QUADS: &str = "\
+# two quads sharing an edge
+o first
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3 4
+o second
+v 2 0 0
+v 2 1 0
+f 2 -2 -1 3
+";
+
+    #[test]
+    fn load_obj_objects() {
+        let meshes =
+            Mesh::<f32>::obj_from_bytes(QUADS.as_bytes(), &MeshLoadOptions::default()).unwrap();
+
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].name, "first");
+        assert_eq!(meshes[0].data.0.len(), 4);
+        assert_eq!(meshes[0].data.1.len(), 2);
+        assert_eq!(meshes[1].name, "second");
+        assert_eq!(meshes[1].data.1.len(), 2);
+        assert!(meshes[0].data.2.is_none());
+    }
+
+    #[test]
+    fn load_obj_convert_axis() {
+        let options = MeshLoadOptions {
+            up_axis: UpAxis::Z,
+            scale: Vector3::new(2.0, 2.0, 2.0),
+            ..MeshLoadOptions::default()
+        };
+        let meshes = Mesh::<f32>::obj_from_bytes(QUADS.as_bytes(), &options).unwrap();
+
+        // (0, 1, 0) in a Z-up file points backwards in Y-up
+        assert_eq!(meshes[0].data.0[3], Point3::new(0.0, 0.0, -2.0));
+    }
+
+    #[test]
+    fn load_stl_welded() {
+        let mut bytes = vec![0; 80];
+        bytes.extend(&2u32.to_le_bytes());
+        for triangle in &[
+            [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+        ] {
+            bytes.extend(&[0; 12]);
+            for coord in triangle.iter() {
+                bytes.extend(&coord.to_bits().to_le_bytes());
+            }
+            bytes.extend(&[0; 2]);
+        }
+
+        let options = MeshLoadOptions {
+            weld_distance: Some(0.001),
+            ..MeshLoadOptions::default()
+        };
+        let mesh = Mesh::<f32>::stl_from_bytes(&bytes, &options).unwrap();
+
+        assert_eq!(mesh.data.0.len(), 4);
+        assert_eq!(mesh.data.1.len(), 2);
+    }
+}
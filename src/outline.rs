@@ -0,0 +1,617 @@
+//! # Outline module
+//! Generation of 2D collider shapes from the opaque region of sprites, so their
+//! polygons don't have to be authored by hand.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::outline::{AlphaMask, OutlineOptions};
+//!
+//! let options = OutlineOptions {
+//!     pixel_size: 1.0 / 32.0,
+//!     tolerance: 1.5,
+//!     ..OutlineOptions::default()
+//! };
+//! let shape = AlphaMask::load_png("assets/crate.png")?.convex_shape(&options)?;
+//! ```
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use nalgebra::{convert, Point2, RealField};
+use nphysics::math::Isometry;
+
+use crate::{colliders::Shape, error::PhysicsError};
+
+#[cfg(feature = "png")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "png")]
+use crate::image;
+
+/// `OutlineOptions` control how the outline of an `AlphaMask` is traced and
+/// converted into world coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutlineOptions<N: RealField> {
+    /// Pixels with an opacity of at least this value, within `[0, 1]`, are
+    /// considered solid.
+    ///
+    /// default: `0.5`
+    pub threshold: f64,
+
+    /// The maximum distance, in pixels, the simplified outline may deviate from
+    /// the traced one.
+    ///
+    /// default: `1.0`
+    pub tolerance: N,
+
+    /// The size of a pixel in world units.
+    ///
+    /// default: `1.0`
+    pub pixel_size: N,
+
+    /// Whether the origin of the shape is the center of the image instead of
+    /// its bottom left corner.
+    ///
+    /// default: `true`
+    pub centered: bool,
+}
+
+impl<N: RealField> Default for OutlineOptions<N> {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            tolerance: N::one(),
+            pixel_size: N::one(),
+            centered: true,
+        }
+    }
+}
+
+/// An `AlphaMask` holds the opacity of every pixel of an image, row by row
+/// starting at the top left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlphaMask {
+    width: usize,
+    height: usize,
+    alpha: Vec<f64>,
+}
+
+impl AlphaMask {
+    /// Creates an `AlphaMask` from one 8 bit opacity value per pixel.
+    pub fn from_alpha(width: usize, height: usize, alpha: &[u8]) -> Result<Self, PhysicsError> {
+        Self::from_samples(width, height, alpha, 1)
+    }
+
+    /// Creates an `AlphaMask` from the alpha channel of 8 bit RGBA pixels.
+    pub fn from_rgba(width: usize, height: usize, rgba: &[u8]) -> Result<Self, PhysicsError> {
+        Self::from_samples(width, height, rgba, 4)
+    }
+
+    /// Loads an `AlphaMask` from the PNG image at the given path. Images
+    /// without alpha channel are fully opaque.
+    #[cfg(feature = "png")]
+    pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Self, PhysicsError> {
+        Self::from_png_bytes(&fs::read(path)?)
+    }
+
+    /// Decodes an `AlphaMask` from a PNG image. Images without alpha channel
+    /// are fully opaque.
+    #[cfg(feature = "png")]
+    pub fn from_png_bytes(bytes: &[u8]) -> Result<Self, PhysicsError> {
+        let bitmap = image::decode_png(bytes)?;
+        let alpha = (0..bitmap.height)
+            .flat_map(|y| (0..bitmap.width).map(move |x| (x, y)))
+            .map(|(x, y)| bitmap.alpha(x, y))
+            .collect();
+
+        Ok(Self {
+            width: bitmap.width,
+            height: bitmap.height,
+            alpha,
+        })
+    }
+
+    fn from_samples(
+        width: usize,
+        height: usize,
+        samples: &[u8],
+        channels: usize,
+    ) -> Result<Self, PhysicsError> {
+        if samples.len() != width * height * channels {
+            return Err(PhysicsError::InvalidImage {
+                format: "raw",
+                reason: format!(
+                    "expected {} bytes for {}x{} pixels, got {}",
+                    width * height * channels,
+                    width,
+                    height,
+                    samples.len()
+                ),
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            alpha: samples
+                .chunks(channels)
+                .map(|pixel| f64::from(pixel[channels - 1]) / 255.0)
+                .collect(),
+        })
+    }
+
+    /// Traces the outlines of the opaque region using marching squares and
+    /// simplifies them. Outer outlines run counter-clockwise, outlines of holes
+    /// clockwise.
+    pub fn outlines<N: RealField>(&self, options: &OutlineOptions<N>) -> Vec<Vec<Point2<N>>> {
+        self.trace(options.threshold)
+            .into_iter()
+            .map(|outline| simplify_loop(&outline, options.tolerance))
+            .filter(|outline| outline.len() >= 3)
+            .map(|outline| {
+                // flipping the y axis reverses the winding of the traced outlines
+                outline
+                    .into_iter()
+                    .rev()
+                    .map(|point| self.to_world(point, options))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Creates a `Shape::Polyline` following all outlines of the opaque region,
+    /// including the ones of holes.
+    pub fn polyline_shape<N: RealField>(
+        &self,
+        options: &OutlineOptions<N>,
+    ) -> Result<Shape<N>, PhysicsError> {
+        let mut points = Vec::new();
+        let mut indices = Vec::new();
+        for outline in self.outlines(options) {
+            let offset = points.len();
+            let len = outline.len();
+            indices.extend((0..len).map(|i| Point2::new(offset + i, offset + (i + 1) % len)));
+            points.extend(outline);
+        }
+
+        if points.is_empty() {
+            return Err(PhysicsError::NotEnoughPoints {
+                shape: "AlphaMask",
+                required: 3,
+                actual: 0,
+            });
+        }
+
+        Ok(Shape::Polyline {
+            points,
+            indices: Some(indices),
+        })
+    }
+
+    /// Creates a `Shape::Compound` of `Shape::ConvexPolygon`s covering the
+    /// opaque region. Holes are filled, as they can't be represented by a
+    /// decomposition into convex parts of the outer outline.
+    pub fn convex_shape<N: RealField>(
+        &self,
+        options: &OutlineOptions<N>,
+    ) -> Result<Shape<N>, PhysicsError> {
+        let parts = self
+            .outlines(options)
+            .into_iter()
+            .filter(|outline| signed_area(outline) > N::zero())
+            .flat_map(|outline| convex_decomposition(&outline))
+            .map(|points| (Isometry::identity(), Shape::ConvexPolygon { points }))
+            .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            return Err(PhysicsError::EmptyCompound);
+        }
+
+        Ok(Shape::Compound { parts })
+    }
+
+    fn is_solid(&self, x: isize, y: isize, threshold: f64) -> bool {
+        self.value(x, y) >= threshold
+    }
+
+    /// Returns the opacity of the given pixel; pixels next to the image are
+    /// transparent, so all outlines are closed.
+    fn value(&self, x: isize, y: isize) -> f64 {
+        match x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            true => self.alpha[y as usize * self.width + x as usize],
+            false => 0.0,
+        }
+    }
+
+    /// Traces the closed outlines of the opaque region in pixel coordinates,
+    /// with the pixel centers at whole numbers.
+    ///
+    /// Every cell of four neighbouring pixels is walked along its corners.
+    /// Where the walk leaves the opaque region, an outline segment starts and
+    /// leads to where the walk enters it again, so the opaque region always
+    /// lies on the same side of the outline. Segments are identified by the
+    /// cell edges they cross, which are shared with the neighbouring cells.
+    fn trace(&self, threshold: f64) -> Vec<Vec<Point2<f64>>> {
+        let mut segments = HashMap::new();
+
+        for y in -1..self.height as isize {
+            for x in -1..self.width as isize {
+                let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+                let crossings = (0..4)
+                    .filter_map(|i| {
+                        let start = corners[i];
+                        let end = corners[(i + 1) % 4];
+                        let leaving = self.is_solid(start.0, start.1, threshold);
+                        match leaving != self.is_solid(end.0, end.1, threshold) {
+                            true => Some((edge_key(start, end), leaving)),
+                            false => None,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                // two diagonal opaque pixels are connected if the center of the
+                // cell is opaque as well
+                let connected = crossings.len() == 4
+                    && corners
+                        .iter()
+                        .map(|corner| self.value(corner.0, corner.1))
+                        .sum::<f64>()
+                        / 4.0
+                        >= threshold;
+
+                for (i, (key, leaving)) in crossings.iter().enumerate() {
+                    if *leaving {
+                        let len = crossings.len();
+                        let entering = match connected {
+                            true => crossings[(i + 1) % len].0,
+                            false => crossings[(i + len - 1) % len].0,
+                        };
+                        segments.insert(*key, entering);
+                    }
+                }
+            }
+        }
+
+        // chain the segments into closed outlines
+        let mut outlines = Vec::new();
+        while let Some(start) = segments.keys().next().cloned() {
+            let mut outline = Vec::new();
+            let mut current = start;
+            while let Some(next) = segments.remove(&current) {
+                outline.push(self.crossing(current, threshold));
+                current = next;
+            }
+            outlines.push(outline);
+        }
+
+        outlines
+    }
+
+    /// Interpolates where the opacity crosses the threshold along an edge.
+    fn crossing(&self, key: EdgeKey, threshold: f64) -> Point2<f64> {
+        let (start, end) = key;
+        let a = self.value(start.0, start.1);
+        let b = self.value(end.0, end.1);
+        let t = match (b - a).abs() > std::f64::EPSILON {
+            true => ((threshold - a) / (b - a)).max(0.0).min(1.0),
+            false => 0.5,
+        };
+
+        Point2::new(
+            start.0 as f64 + (end.0 - start.0) as f64 * t,
+            start.1 as f64 + (end.1 - start.1) as f64 * t,
+        )
+    }
+
+    /// Converts pixel coordinates into world coordinates; the y axis is
+    /// flipped, which turns the traced outlines counter-clockwise.
+    fn to_world<N: RealField>(&self, point: Point2<f64>, options: &OutlineOptions<N>) -> Point2<N> {
+        let (x, y) = match options.centered {
+            true => (
+                point.x + 0.5 - self.width as f64 / 2.0,
+                self.height as f64 / 2.0 - point.y - 0.5,
+            ),
+            false => (point.x + 0.5, self.height as f64 - point.y - 0.5),
+        };
+
+        Point2::new(
+            convert::<f64, N>(x) * options.pixel_size,
+            convert::<f64, N>(y) * options.pixel_size,
+        )
+    }
+}
+
+/// The two pixels of a cell edge, ordered so both cells sharing the edge use
+/// the same key.
+type EdgeKey = ((isize, isize), (isize, isize));
+
+fn edge_key(a: (isize, isize), b: (isize, isize)) -> EdgeKey {
+    match a < b {
+        true => (a, b),
+        false => (b, a),
+    }
+}
+
+/// Simplifies a closed outline with the Douglas-Peucker algorithm, splitting it
+/// at its lowest point along the x axis and the point farthest away from it,
+/// which both are corners that can't be removed.
+fn simplify_loop<N: RealField>(outline: &[Point2<f64>], tolerance: N) -> Vec<Point2<f64>> {
+    let tolerance = nalgebra::try_convert::<N, f64>(tolerance).unwrap_or(0.0);
+    if outline.len() < 4 {
+        return outline.to_vec();
+    }
+
+    let start = (0..outline.len())
+        .min_by(|a, b| {
+            let (a, b) = (outline[*a], outline[*b]);
+            (a.x, a.y)
+                .partial_cmp(&(b.x, b.y))
+                .unwrap_or(Ordering::Equal)
+        })
+        .unwrap_or(0);
+    let mut rotated = outline[start..].to_vec();
+    rotated.extend_from_slice(&outline[..start]);
+    let outline = &rotated;
+
+    let first = outline[0];
+    let (farthest, _) = outline
+        .iter()
+        .enumerate()
+        .map(|(i, point)| (i, nalgebra::distance_squared(&first, point)))
+        .fold((0, 0.0), |best, current| match current.1 > best.1 {
+            true => current,
+            false => best,
+        });
+
+    let mut first_half = simplify(&outline[..=farthest], tolerance);
+    let mut closing = outline[farthest..].to_vec();
+    closing.push(first);
+    let second_half = simplify(&closing, tolerance);
+
+    // both halves share their end points
+    first_half.pop();
+    first_half.extend(&second_half[..second_half.len() - 1]);
+    first_half
+}
+
+/// Simplifies an open polyline with the Douglas-Peucker algorithm, keeping its
+/// end points.
+fn simplify(points: &[Point2<f64>], tolerance: f64) -> Vec<Point2<f64>> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let (index, distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, point)| (i + 1, segment_distance(point, &first, &last)))
+        .fold((0, 0.0), |best, current| match current.1 > best.1 {
+            true => current,
+            false => best,
+        });
+
+    match distance > tolerance {
+        true => {
+            let mut simplified = simplify(&points[..=index], tolerance);
+            simplified.pop();
+            simplified.extend(simplify(&points[index..], tolerance));
+            simplified
+        }
+        false => vec![first, last],
+    }
+}
+
+fn segment_distance(point: &Point2<f64>, a: &Point2<f64>, b: &Point2<f64>) -> f64 {
+    let ab = b - a;
+    let len_squared = ab.norm_squared();
+    if len_squared <= std::f64::EPSILON {
+        return nalgebra::distance(point, a);
+    }
+
+    let t = ((point - a).dot(&ab) / len_squared).max(0.0).min(1.0);
+    nalgebra::distance(point, &(a + ab * t))
+}
+
+fn signed_area<N: RealField>(outline: &[Point2<N>]) -> N {
+    let mut area = N::zero();
+    for i in 0..outline.len() {
+        let a = outline[i];
+        let b = outline[(i + 1) % outline.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    area / convert(2.0)
+}
+
+fn cross<N: RealField>(a: &Point2<N>, b: &Point2<N>, c: &Point2<N>) -> N {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// Decomposes a simple counter-clockwise polygon into convex polygons by
+/// triangulating it through ear clipping and merging adjacent triangles as
+/// long as the result stays convex (Hertel-Mehlhorn).
+fn convex_decomposition<N: RealField>(outline: &[Point2<N>]) -> Vec<Vec<Point2<N>>> {
+    let mut polygons = triangulate(outline);
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..polygons.len() {
+            for j in i + 1..polygons.len() {
+                if let Some(polygon) = merge(&polygons[i], &polygons[j], outline) {
+                    polygons[i] = polygon;
+                    polygons.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    polygons
+        .into_iter()
+        .map(|polygon| polygon.into_iter().map(|i| outline[i]).collect())
+        .collect()
+}
+
+/// Triangulates a simple counter-clockwise polygon through ear clipping,
+/// returning the indices of the triangles.
+fn triangulate<N: RealField>(outline: &[Point2<N>]) -> Vec<Vec<usize>> {
+    let mut remaining = (0..outline.len()).collect::<Vec<_>>();
+    let mut triangles = Vec::new();
+
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|i| {
+            let (a, b, c) = (
+                remaining[(i + len - 1) % len],
+                remaining[*i],
+                remaining[(i + 1) % len],
+            );
+            cross(&outline[a], &outline[b], &outline[c]) > N::zero()
+                && remaining.iter().all(|other| {
+                    [a, b, c].contains(other)
+                        || !in_triangle(&outline[*other], &outline[a], &outline[b], &outline[c])
+                })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push(vec![
+                    remaining[(i + len - 1) % len],
+                    remaining[i],
+                    remaining[(i + 1) % len],
+                ]);
+                remaining.remove(i);
+            }
+            None => {
+                // only degenerate, e.g. collinear, vertices are left
+                warn!("Failed to triangulate the remaining outline, skipping it");
+                break;
+            }
+        }
+    }
+    if remaining.len() == 3 {
+        triangles.push(remaining);
+    }
+
+    triangles
+}
+
+fn in_triangle<N: RealField>(p: &Point2<N>, a: &Point2<N>, b: &Point2<N>, c: &Point2<N>) -> bool {
+    cross(a, b, p) >= N::zero() && cross(b, c, p) >= N::zero() && cross(c, a, p) >= N::zero()
+}
+
+/// Merges two counter-clockwise polygons sharing an edge if the result is
+/// convex.
+fn merge<N: RealField>(a: &[usize], b: &[usize], outline: &[Point2<N>]) -> Option<Vec<usize>> {
+    // the shared edge runs from u to v in `a` and from v to u in `b`
+    let (i, j) = (0..a.len()).find_map(|i| {
+        let (u, v) = (a[i], a[(i + 1) % a.len()]);
+        (0..b.len())
+            .find(|j| b[*j] == v && b[(j + 1) % b.len()] == u)
+            .map(|j| (i, j))
+    })?;
+
+    // walk `a` from v around to u, then `b` from after u to before v
+    let mut merged = (0..a.len())
+        .map(|k| a[(i + 1 + k) % a.len()])
+        .collect::<Vec<_>>();
+    merged.extend((0..b.len() - 2).map(|k| b[(j + 2 + k) % b.len()]));
+
+    let len = merged.len();
+    let convex = (0..len).all(|k| {
+        cross(
+            &outline[merged[k]],
+            &outline[merged[(k + 1) % len]],
+            &outline[merged[(k + 2) % len]],
+        ) >= N::zero()
+    });
+
+    match convex {
+        true => Some(merged),
+        false => None,
+    }
+}
+
+#[cfg(all(test, feature = "physics2d"))]
+mod tests {
+    use super::{signed_area, AlphaMask, OutlineOptions};
+    use crate::colliders::Shape;
+
+    #[rustfmt::skip]
+    const L_SHAPE: [u8; 16] = [
+        255, 0,   0,   0,
+        255, 0,   0,   0,
+        255, 0,   0,   0,
+        255, 255, 255, 0,
+    ];
+
+    #[test]
+    fn trace_outline() {
+        let mask = AlphaMask::from_alpha(4, 4, &L_SHAPE).unwrap();
+        let outlines = mask.outlines(&OutlineOptions::<f32> {
+            tolerance: 0.1,
+            ..OutlineOptions::default()
+        });
+
+        // the corners are cut diagonally
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(outlines[0].len(), 10);
+        assert!(signed_area(&outlines[0]) > 0.0);
+    }
+
+    #[test]
+    fn trace_hole() {
+        #[rustfmt::skip]
+        let ring = [
+            255, 255, 255, 255,
+            255, 0,   0,   255,
+            255, 0,   0,   255,
+            255, 255, 255, 255,
+        ];
+        let mask = AlphaMask::from_alpha(4, 4, &ring).unwrap();
+        let mut areas = mask
+            .outlines(&OutlineOptions::<f32>::default())
+            .iter()
+            .map(|outline| signed_area(outline))
+            .collect::<Vec<_>>();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // the hole runs clockwise within the counter-clockwise outer outline
+        assert_eq!(areas.len(), 2);
+        assert!(areas[0] < 0.0 && areas[1] > 0.0);
+        assert!(areas[1] > -areas[0]);
+    }
+
+    #[test]
+    fn decompose_outline() {
+        let mask = AlphaMask::from_alpha(4, 4, &L_SHAPE).unwrap();
+        let options = OutlineOptions::<f32> {
+            tolerance: 0.1,
+            ..OutlineOptions::default()
+        };
+        let area = signed_area(&mask.outlines(&options)[0]);
+
+        match mask.convex_shape(&options).unwrap() {
+            Shape::Compound { parts } => {
+                assert!(parts.len() >= 2);
+                let parts_area: f32 = parts
+                    .iter()
+                    .map(|(_, part)| match part {
+                        Shape::ConvexPolygon { points } => signed_area(points),
+                        _ => panic!("expected a ConvexPolygon"),
+                    })
+                    .sum();
+                assert!((parts_area - area).abs() < 1.0e-4);
+            }
+            _ => panic!("expected a Compound"),
+        }
+    }
+
+    #[test]
+    fn reject_wrong_size() {
+        assert!(AlphaMask::from_rgba(2, 2, &[0; 15]).is_err());
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::ops::{Deref, DerefMut};
 
+use crate::error::PhysicsError;
 use nalgebra::{convert, RealField, Scalar};
 use nphysics::math::Vector;
 use nphysics::solver::IntegrationParameters;
@@ -90,10 +91,97 @@ impl Default for PhysicsProfilingEnabled {
     }
 }
 
-/// Essentially identical to the nphysics IntegrationParameters struct except
-/// without the t and dt fields. Manages the details of physics integration.
+/// The `PhysicsControl` is used to pause, slow down or speed up the
+/// simulation, e.g. for menu pauses, bullet-time effects or stepping through
+/// the simulation frame by frame.
+///
+/// The `PhysicsStepperSystem` scales the timestep of each step by
+/// `time_scale` without changing the timestep of the nphysics World itself,
+/// so scaling doesn't count as a `TimeStep` change.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PhysicsControl<N: RealField> {
+    /// Skips all steps while set.
+    ///
+    /// default: `false`
+    pub paused: bool,
+
+    /// The factor the timestep of each step is multiplied with. A value of
+    /// `0.0` or less is treated like a pause.
+    ///
+    /// default: `1.0`
+    pub time_scale: N,
+
+    /// Requests a single step while `paused` is set. The step is performed by
+    /// the next run of the `PhysicsStepperSystem`, which then pauses the
+    /// simulation again.
+    ///
+    /// default: `false`
+    pub step_once: bool,
+}
+
+impl<N: RealField> PhysicsControl<N> {
+    /// Pauses the simulation.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes a paused simulation.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.step_once = false;
+    }
+
+    /// Advances a paused simulation by exactly one step and pauses it again.
+    pub fn step_once(&mut self) {
+        self.paused = true;
+        self.step_once = true;
+    }
+
+    /// Returns the factor the timestep of the upcoming step is multiplied
+    /// with, which is zero if no step is going to be performed.
+    pub fn effective_time_scale(&self) -> N {
+        if (self.paused && !self.step_once) || self.time_scale <= N::zero() {
+            N::zero()
+        } else {
+            self.time_scale
+        }
+    }
+}
+
+impl<N: RealField> Default for PhysicsControl<N> {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            time_scale: N::one(),
+            step_once: false,
+        }
+    }
+}
+
+/// Essentially identical to the nphysics IntegrationParameters struct.
+/// Manages the details of physics integration.
+///
+/// The `SyncParametersToPhysicsSystem` populates this resource from the
+/// nphysics World on setup, applies it whenever it is modified and afterwards
+/// reads the values back, e.g. the `elapsed_time`. Out of range values are
+/// rejected as a whole and reverted to the current values of the World.
+///
+/// nphysics 0.12 has no separate error reduction parameter for multibody
+/// joints, they use `error_reduction_parameter` as well.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct PhysicsIntegrationParameters<N: RealField> {
+    /// The timestep length. If a `TimeStep` resource exists, it takes
+    /// precedence.
+    ///
+    /// default: `1.0 / 60.0`
+    pub time_step: N,
+
+    /// The total elapsed time in the physics world, accumulated from the
+    /// `time_step` of every step.
+    ///
+    /// default: `0.0`
+    pub elapsed_time: N,
+
     /// The `[0,1]` proportion of the positional error to be corrected at each
     /// time step.
     ///
@@ -153,10 +241,117 @@ pub struct PhysicsIntegrationParameters<N: RealField> {
     ///
     /// default: `3`
     pub max_position_iterations: usize,
+
+    /// Maximum number of iterations performed by the position-based constraints
+    /// solver for CCD steps. If CCD causes bodies to stutter, this may be too
+    /// low to resolve all penetrations.
+    ///
+    /// default: `10`
+    pub max_ccd_position_iterations: usize,
+
+    /// Maximum number of CCD substeps performed per step. CCD is disabled
+    /// entirely if this is `0`.
+    ///
+    /// default: `1`
+    pub max_ccd_substeps: usize,
+
+    /// If set, sensors generate proximity events every time a collider enters
+    /// or leaves them during the CCD substeps of a single step, instead of at
+    /// most once.
+    ///
+    /// default: `false`
+    pub multiple_ccd_substep_sensor_events_enabled: bool,
+
+    /// If set, penetrating colliders are considered to have a time of impact of
+    /// zero, which prevents tunnelling almost completely but may cause
+    /// stuttering.
+    ///
+    /// default: `false`
+    pub ccd_on_penetration_enabled: bool,
+
+    /// If set, a step stops right after resolving exactly one CCD event,
+    /// allowing to take action in-between two CCD events.
+    ///
+    /// default: `false`
+    pub return_after_ccd_substep: bool,
 }
 
 impl<N: RealField> PhysicsIntegrationParameters<N> {
-    pub(crate) fn apply(&self, to: &mut IntegrationParameters<N>) {
+    /// Checks that all parameters are within their valid range.
+    pub fn validate(&self) -> Result<(), PhysicsError> {
+        let unit_interval = |value: N| value >= N::zero() && value <= N::one();
+        let non_negative = |value: N| value >= N::zero() && value.is_finite();
+
+        let checks = [
+            (
+                "time_step",
+                non_negative(self.time_step),
+                "a finite value >= 0",
+            ),
+            (
+                "elapsed_time",
+                non_negative(self.elapsed_time),
+                "a finite value >= 0",
+            ),
+            (
+                "error_reduction_parameter",
+                unit_interval(self.error_reduction_parameter),
+                "a value in [0, 1]",
+            ),
+            (
+                "warmstart_coefficient",
+                unit_interval(self.warmstart_coefficient),
+                "a value in [0, 1]",
+            ),
+            (
+                "restitution_velocity_threshold",
+                non_negative(self.restitution_velocity_threshold),
+                "a finite value >= 0",
+            ),
+            (
+                "allowed_linear_error",
+                non_negative(self.allowed_linear_error),
+                "a finite value >= 0",
+            ),
+            (
+                "allowed_angular_error",
+                non_negative(self.allowed_angular_error),
+                "a finite value >= 0",
+            ),
+            (
+                "max_linear_correction",
+                non_negative(self.max_linear_correction),
+                "a finite value >= 0",
+            ),
+            (
+                "max_angular_correction",
+                non_negative(self.max_angular_correction),
+                "a finite value >= 0",
+            ),
+            (
+                "max_stabilization_multiplier",
+                non_negative(self.max_stabilization_multiplier),
+                "a finite value >= 0",
+            ),
+        ];
+
+        match checks.iter().find(|(_, valid, _)| !valid) {
+            Some((parameter, _, expected)) => Err(PhysicsError::InvalidParameter {
+                parameter: *parameter,
+                expected: *expected,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the parameters to the nphysics `World`. The elapsed time keeps
+    /// advancing with every step, so it is only overwritten if it differs from
+    /// the `synced` parameters the `World` was last read back into.
+    pub(crate) fn apply(&self, synced: Option<&Self>, to: &mut IntegrationParameters<N>) {
+        to.set_dt(self.time_step);
+        if synced.map_or(true, |synced| synced.elapsed_time != self.elapsed_time) {
+            to.t = self.elapsed_time;
+        }
         to.erp = self.error_reduction_parameter;
         to.warmstart_coeff = self.warmstart_coefficient;
         to.restitution_velocity_threshold = self.restitution_velocity_threshold;
@@ -167,12 +362,20 @@ impl<N: RealField> PhysicsIntegrationParameters<N> {
         to.max_stabilization_multiplier = self.max_stabilization_multiplier;
         to.max_velocity_iterations = self.max_velocity_iterations;
         to.max_position_iterations = self.max_position_iterations;
+        to.max_ccd_position_iterations = self.max_ccd_position_iterations;
+        to.max_ccd_substeps = self.max_ccd_substeps;
+        to.multiple_ccd_substep_sensor_events_enabled =
+            self.multiple_ccd_substep_sensor_events_enabled;
+        to.ccd_on_penetration_enabled = self.ccd_on_penetration_enabled;
+        to.return_after_ccd_substep = self.return_after_ccd_substep;
     }
 }
 
 impl<N: RealField> PartialEq<IntegrationParameters<N>> for PhysicsIntegrationParameters<N> {
     fn eq(&self, other: &IntegrationParameters<N>) -> bool {
-        self.error_reduction_parameter == other.erp
+        self.time_step == other.dt()
+            && self.elapsed_time == other.t
+            && self.error_reduction_parameter == other.erp
             && self.warmstart_coefficient == other.warmstart_coeff
             && self.restitution_velocity_threshold == other.restitution_velocity_threshold
             && self.allowed_linear_error == other.allowed_linear_error
@@ -182,12 +385,45 @@ impl<N: RealField> PartialEq<IntegrationParameters<N>> for PhysicsIntegrationPar
             && self.max_stabilization_multiplier == other.max_stabilization_multiplier
             && self.max_velocity_iterations == other.max_velocity_iterations
             && self.max_position_iterations == other.max_position_iterations
+            && self.max_ccd_position_iterations == other.max_ccd_position_iterations
+            && self.max_ccd_substeps == other.max_ccd_substeps
+            && self.multiple_ccd_substep_sensor_events_enabled
+                == other.multiple_ccd_substep_sensor_events_enabled
+            && self.ccd_on_penetration_enabled == other.ccd_on_penetration_enabled
+            && self.return_after_ccd_substep == other.return_after_ccd_substep
+    }
+}
+
+impl<N: RealField> From<&IntegrationParameters<N>> for PhysicsIntegrationParameters<N> {
+    fn from(params: &IntegrationParameters<N>) -> Self {
+        PhysicsIntegrationParameters {
+            time_step: params.dt(),
+            elapsed_time: params.t,
+            error_reduction_parameter: params.erp,
+            warmstart_coefficient: params.warmstart_coeff,
+            restitution_velocity_threshold: params.restitution_velocity_threshold,
+            allowed_linear_error: params.allowed_linear_error,
+            allowed_angular_error: params.allowed_angular_error,
+            max_linear_correction: params.max_linear_correction,
+            max_angular_correction: params.max_angular_correction,
+            max_stabilization_multiplier: params.max_stabilization_multiplier,
+            max_velocity_iterations: params.max_velocity_iterations,
+            max_position_iterations: params.max_position_iterations,
+            max_ccd_position_iterations: params.max_ccd_position_iterations,
+            max_ccd_substeps: params.max_ccd_substeps,
+            multiple_ccd_substep_sensor_events_enabled: params
+                .multiple_ccd_substep_sensor_events_enabled,
+            ccd_on_penetration_enabled: params.ccd_on_penetration_enabled,
+            return_after_ccd_substep: params.return_after_ccd_substep,
+        }
     }
 }
 
 impl<N: RealField> Default for PhysicsIntegrationParameters<N> {
     fn default() -> Self {
         PhysicsIntegrationParameters {
+            time_step: convert(1.0 / 60.0),
+            elapsed_time: N::zero(),
             error_reduction_parameter: convert(0.2),
             warmstart_coefficient: convert(1.0),
             restitution_velocity_threshold: convert(1.0),
@@ -198,6 +434,11 @@ impl<N: RealField> Default for PhysicsIntegrationParameters<N> {
             max_stabilization_multiplier: convert(0.2),
             max_velocity_iterations: 8,
             max_position_iterations: 3,
+            max_ccd_position_iterations: 10,
+            max_ccd_substeps: 1,
+            multiple_ccd_substep_sensor_events_enabled: false,
+            ccd_on_penetration_enabled: false,
+            return_after_ccd_substep: false,
         }
     }
 }
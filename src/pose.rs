@@ -2,10 +2,10 @@ use nalgebra::RealField;
 use specs::{Component, DenseVecStorage, FlaggedStorage};
 use std::ops::{Deref, DerefMut};
 
-use nphysics::math::{Isometry, Point};
+use nphysics::math::{Isometry, Point, Vector};
 
-#[cfg(feature = "physics2d")]
-use amethyst_core::math::{Translation2, UnitComplex};
+#[cfg(all(feature = "amethyst", feature = "physics2d"))]
+use amethyst_core::math::{Translation2, UnitComplex, Vector2};
 
 /// A `Pose` is a position and an orientation. They are wrapped together into an
 /// isometry for use in the physics engine.
@@ -22,6 +22,14 @@ pub trait Pose<N: RealField>:
     fn isometry(&self) -> Isometry<N>;
     fn set_isometry(&mut self, isometry: Isometry<N>) -> &mut Self;
 
+    /// Returns the scale of this `Pose` along each axis, which is applied to
+    /// the `Shape` of its colliders. Negative factors mirror them. Changing it
+    /// recreates the colliders.
+    /// Defaults to no scaling.
+    fn scale(&self) -> Vector<N> {
+        Vector::repeat(N::one())
+    }
+
     /// Helper function to extract the location of this `Pose`. Using
     /// `Pose::isometry()` is preferable, but can be harder to work with.
     /// The translation of this `Pose` can be set using `Pose::
@@ -57,6 +65,10 @@ impl Pose<f32> for amethyst_core::Transform {
     fn set_isometry(&mut self, isometry: Isometry<f32>) -> &mut Self {
         self.set_isometry(isometry)
     }
+
+    fn scale(&self) -> Vector<f32> {
+        *self.scale()
+    }
 }
 
 #[cfg(feature = "amethyst")]
@@ -78,6 +90,11 @@ impl Pose<f32> for amethyst_core::Transform {
         self.set_translation_y(isometry.translation.y);
         self
     }
+
+    fn scale(&self) -> Vector<f32> {
+        let scale = self.scale();
+        Vector2::new(scale.x, scale.y)
+    }
 }
 
 pub struct SimplePosition<N: RealField>(pub Isometry<N>);
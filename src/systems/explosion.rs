@@ -0,0 +1,223 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use specs::{world::Index, Entities, Read, ReadExpect, System, Write, WriteExpect};
+
+use crate::{events::{ExplosionHit, ExplosionHits},
+            explosions::{Explosion, Explosions},
+            Physics};
+use nalgebra::RealField;
+use ncollide::bounding_volume::AABB;
+use ncollide::query::Ray;
+use nphysics::math::{ForceType, Point, Vector};
+use nphysics::object::{Body, BodyPart, DefaultBodyHandle, DefaultBodySet, DefaultColliderSet};
+use nphysics::world::DefaultGeometricalWorld;
+
+/// The `ExplosionSystem` handles the `Explosion`s queued in the `Explosions`
+/// resource by applying impulses to the affected bodies and emitting
+/// `ExplosionHit` events.
+pub struct ExplosionSystem<N>(PhantomData<N>);
+
+impl<'s, N: RealField> System<'s> for ExplosionSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, Explosions<N>>,
+        Write<'s, ExplosionHits<N>>,
+        Read<'s, Physics<N>>,
+        WriteExpect<'s, DefaultBodySet<N>>,
+        ReadExpect<'s, DefaultColliderSet<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut explosions, mut explosion_hits, physics, mut bodies, colliders) = data;
+
+        for explosion in explosions.drain(..) {
+            // find the closest point of every body with a collider within the radius
+            let extents = Vector::repeat(explosion.radius);
+            let aabb = AABB::new(explosion.center - extents, explosion.center + extents);
+            let mut closest: HashMap<DefaultBodyHandle, (N, Point<N>)> = HashMap::new();
+            for (_, collider) in physics.geometric_world.interferences_with_aabb(
+                &*colliders,
+                &aabb,
+                &explosion.groups,
+            ) {
+                if collider.is_sensor() {
+                    continue;
+                }
+
+                let projection = match collider.shape().as_point_query() {
+                    Some(query) => {
+                        query.project_point(collider.position(), &explosion.center, true)
+                    }
+                    None => continue,
+                };
+                let distance = match projection.is_inside {
+                    true => N::zero(),
+                    false => (projection.point - explosion.center).norm(),
+                };
+                if distance > explosion.radius {
+                    continue;
+                }
+
+                let body = collider.body();
+                if closest
+                    .get(&body)
+                    .map_or(true, |(closest, _)| distance < *closest)
+                {
+                    closest.insert(body, (distance, projection.point));
+                }
+            }
+
+            for (handle, (distance, point)) in closest {
+                if explosion.occlusion
+                    && occluded(
+                        &explosion,
+                        &point,
+                        handle,
+                        &physics.geometric_world,
+                        &colliders,
+                    )
+                {
+                    continue;
+                }
+
+                let rigid_body = match bodies.rigid_body_mut(handle) {
+                    Some(rigid_body) => rigid_body,
+                    None => continue,
+                };
+                let entity = match rigid_body
+                    .user_data()
+                    .and_then(|user_data| user_data.downcast_ref::<Index>())
+                {
+                    Some(id) => entities.entity(*id),
+                    None => continue,
+                };
+
+                // push the body away from the center; if the explosion happened inside
+                // of it, push it through its center of mass instead
+                let direction = [point, rigid_body.center_of_mass()]
+                    .iter()
+                    .map(|target| target - explosion.center)
+                    .find(|direction| direction.norm() > N::default_epsilon())
+                    .map_or_else(Vector::zeros, |direction| direction.normalize());
+                let impulse = direction * explosion.impulse_at(distance);
+
+                if rigid_body.is_dynamic() {
+                    rigid_body.apply_force_at_point(0, &impulse, &point, ForceType::Impulse, true);
+                }
+
+                explosion_hits.single_write(ExplosionHit {
+                    entity,
+                    impulse,
+                    point,
+                });
+            }
+        }
+    }
+}
+
+/// Returns `true` if a non-sensor collider of another body lies between the
+/// center of the explosion and the given point.
+fn occluded<N: RealField>(
+    explosion: &Explosion<N>,
+    point: &Point<N>,
+    body: DefaultBodyHandle,
+    geometric_world: &DefaultGeometricalWorld<N>,
+    colliders: &DefaultColliderSet<N>,
+) -> bool {
+    let ray = Ray::new(explosion.center, point - explosion.center);
+    if ray.dir.norm() <= N::default_epsilon() {
+        return false;
+    }
+
+    // the time of impact is relative to the length of the ray, which ends at the
+    // point
+    geometric_world
+        .interferences_with_ray(colliders, &ray, &explosion.groups)
+        .any(|(_, collider, intersection)| {
+            !collider.is_sensor() && collider.body() != body && intersection.toi < N::one()
+        })
+}
+
+impl<N> Default for ExplosionSystem<N> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use specs::{world::Builder, World};
+
+    use crate::{colliders::Shape,
+                events::ExplosionHits,
+                explosions::{Explosion, Explosions},
+                physics_dispatcher, PhysicsBody, PhysicsBodyBuilder, PhysicsColliderBuilder,
+                SimplePosition};
+    use nalgebra::{Isometry3, Point3, Vector3};
+    use nphysics::{force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
+
+    /// Detonates an explosion next to a ball and returns its velocity and the
+    /// amount of `ExplosionHit`s, optionally with a wall in between.
+    fn detonate(wall: bool) -> (Vector3<f32>, usize) {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+        let mut reader_id = world
+            .write_resource::<ExplosionHits<f32>>()
+            .register_reader();
+
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(2.0, 0.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+
+        if wall {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(0.8, 0.0, 0.0)))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+                .with(
+                    PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                        half_extents: Vector3::new(0.1, 2.0, 2.0),
+                    })
+                    .build(),
+                )
+                .build();
+        }
+
+        // let the colliders enter the broad phase first
+        dispatcher.dispatch(&mut world.res);
+
+        world
+            .write_resource::<Explosions<f32>>()
+            .push(Explosion::new(Point3::origin(), 5.0, 10.0).occlusion(true));
+        dispatcher.dispatch(&mut world.res);
+
+        let hits = world
+            .read_resource::<ExplosionHits<f32>>()
+            .read(&mut reader_id)
+            .filter(|hit| hit.entity == ball)
+            .count();
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        (physics_bodies.get(ball).unwrap().velocity.linear, hits)
+    }
+
+    #[test]
+    fn explosion() {
+        let (velocity, hits) = detonate(false);
+        assert!(velocity.x > 0.0);
+        assert_eq!(hits, 1);
+
+        let (velocity, hits) = detonate(true);
+        assert_eq!(velocity.x, 0.0);
+        assert_eq!(hits, 0);
+    }
+}
@@ -3,16 +3,28 @@ use std::ops::Deref;
 use specs::{storage::{ComponentEvent, MaskedStorage},
             BitSet, Component, ReaderId, Storage, Tracked};
 
-pub use self::{physics_stepper::PhysicsStepperSystem,
+use nalgebra::RealField;
+use nphysics::force_generator::{DefaultForceGeneratorHandle, DefaultForceGeneratorSet,
+                                ForceGenerator};
+use nphysics::object::DefaultBodySet;
+
+pub use self::{explosion::ExplosionSystem, physics_stepper::PhysicsStepperSystem,
                sync_bodies_from_physics::SyncBodiesFromPhysicsSystem,
                sync_bodies_to_physics::SyncBodiesToPhysicsSystem,
                sync_colliders_to_physics::SyncCollidersToPhysicsSystem,
+               sync_fluid_volumes_to_physics::SyncFluidVolumesToPhysicsSystem,
+               sync_gravity_fields_to_physics::SyncGravityFieldsToPhysicsSystem,
+               sync_pair_filter_to_physics::SyncPairFilterToPhysicsSystem,
                sync_parameters_to_physics::SyncParametersToPhysicsSystem};
 
+mod explosion;
 mod physics_stepper;
 mod sync_bodies_from_physics;
 mod sync_bodies_to_physics;
 mod sync_colliders_to_physics;
+mod sync_fluid_volumes_to_physics;
+mod sync_gravity_fields_to_physics;
+mod sync_pair_filter_to_physics;
 mod sync_parameters_to_physics;
 
 /// Iterated over the `ComponentEvent::Inserted`s of a given, tracked `Storage`
@@ -46,3 +58,26 @@ where
 
     (inserted, modified, removed)
 }
+
+/// Returns the force generator stored under the given handle, inserting a new
+/// one on the first call or if it was removed from the `ForceGeneratorSet`.
+pub(crate) fn force_generator<'a, N, G>(
+    forces: &'a mut DefaultForceGeneratorSet<N>,
+    handle: &mut Option<DefaultForceGeneratorHandle>,
+) -> &'a mut G
+where
+    N: RealField,
+    G: ForceGenerator<N, DefaultBodySet<N>> + Default,
+{
+    let inserted = handle
+        .and_then(|handle| forces.get(handle))
+        .map_or(false, |generator| generator.is::<G>());
+    if !inserted {
+        *handle = Some(forces.insert(Box::new(G::default())));
+    }
+
+    forces
+        .get_mut(handle.unwrap())
+        .and_then(|generator| generator.downcast_mut::<G>())
+        .unwrap()
+}
@@ -3,7 +3,9 @@ use std::marker::PhantomData;
 use specs::{Entities, Read, System, Write, WriteExpect};
 
 use crate::events::{ContactEvent, ContactEvents, ProximityEvent, ProximityEvents};
-use crate::{parameters::TimeStep, Physics, PhysicsWorld};
+use crate::{filters::lock,
+            parameters::{PhysicsControl, TimeStep},
+            Physics, PhysicsWorld};
 use nalgebra::RealField;
 use nphysics::force_generator::DefaultForceGeneratorSet;
 use nphysics::joint::DefaultJointConstraintSet;
@@ -23,6 +25,7 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
     type SystemData = (
         Entities<'s>,
         Option<Read<'s, TimeStep<N>>>,
+        Option<Write<'s, PhysicsControl<N>>>,
         Write<'s, ContactEvents>,
         Write<'s, ProximityEvents>,
         PhysicsWorld<'s, N>,
@@ -30,8 +33,15 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, time_step, mut contact_events, mut proximity_events, mut physics, storage) =
-            data;
+        let (
+            entities,
+            time_step,
+            control,
+            mut contact_events,
+            mut proximity_events,
+            mut physics,
+            storage,
+        ) = data;
 
         let (mut bodies, mut colliders, mut joints, mut forces) = storage;
 
@@ -54,12 +64,52 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
             }
         }
 
+        // if a PhysicsControl resource exists, skip paused steps and scale the
+        // timestep of this step only
+        let time_scale = match control {
+            Some(mut control) => {
+                let time_scale = control.effective_time_scale();
+                if time_scale == N::zero() {
+                    return;
+                }
+                if control.step_once {
+                    control.step_once = false;
+                    control.paused = true;
+                }
+                time_scale
+            }
+            None => N::one(),
+        };
+
+        // resolve the Entitys of all colliders, which are handed to the pair filters
+        // during the broad phase
+        {
+            let mut pair_filters = lock(&physics.pair_filters);
+            if !pair_filters.is_empty() {
+                let list_handles = physics
+                    .collider_list_handles
+                    .iter()
+                    .flat_map(|(id, handles)| handles.iter().map(move |handle| (id, handle)));
+                pair_filters.update_entities(
+                    &entities,
+                    physics.collider_handles.iter().chain(list_handles),
+                );
+            }
+        }
+
         let Physics {
             ref mut mechanical_world,
             ref mut geometric_world,
             ..
         } = *physics;
 
+        let time_step = mechanical_world.timestep();
+        if time_scale != N::one() {
+            mechanical_world
+                .integration_parameters
+                .set_dt(time_step * time_scale);
+        }
+
         mechanical_world.step(
             geometric_world,
             &mut *bodies,
@@ -68,6 +118,11 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
             &mut *forces,
         );
 
+        // restore the unscaled timestep so it keeps matching the TimeStep resource
+        if time_scale != N::one() {
+            mechanical_world.integration_parameters.set_dt(time_step);
+        }
+
         // Map occurred ncollide ContactEvents to a custom ContactEvent type
         let contact_iter = geometric_world.contact_events().iter();
         contact_events.iter_write(
@@ -88,3 +143,118 @@ impl<N> Default for PhysicsStepperSystem<N> {
         Self(PhantomData)
     }
 }
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use specs::{world::Builder, World};
+
+    use crate::{colliders::Shape, parameters::PhysicsControl, physics_dispatcher, Physics,
+                PhysicsBodyBuilder, PhysicsColliderBuilder, SimplePosition};
+    use nalgebra::{Isometry3, Vector3};
+    use nphysics::{algebra::Velocity3,
+                   force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1.0e-4, "{} != {}", a, b);
+    }
+
+    /// Shoots a small ball at a thin wall and returns where it ended up.
+    fn shoot_at_wall(ccd_enabled: bool) -> f32 {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(5.0, 0.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.01, 5.0, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(200.0, 0.0, 0.0))
+                    .ccd_enabled(ccd_enabled)
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.1 }).build())
+            .build();
+
+        for _ in 0..10 {
+            dispatcher.dispatch(&mut world.res);
+        }
+
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        positions.get(ball).unwrap().0.translation.vector.x
+    }
+
+    #[test]
+    fn ccd_stops_fast_bodies() {
+        // at 200m/s the ball moves more than 3m per step and skips the wall
+        assert!(shoot_at_wall(false) > 5.0);
+        assert!(shoot_at_wall(true) < 5.0);
+    }
+
+    #[test]
+    fn pause_step_once_and_time_scale() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        world.add_resource(PhysicsControl::<f32>::default());
+        dispatcher.setup(&mut world.res);
+
+        let body = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(60.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+        let mut dispatch = |world: &mut World| {
+            dispatcher.dispatch(&mut world.res);
+            let positions = world.read_storage::<SimplePosition<f32>>();
+            positions.get(body).unwrap().0.translation.vector.x
+        };
+
+        // one unit per step at the default timestep of 1/60
+        let x = dispatch(&mut world);
+        assert_near(x, 1.0);
+
+        world.write_resource::<PhysicsControl<f32>>().pause();
+        assert_near(dispatch(&mut world), x);
+
+        world.write_resource::<PhysicsControl<f32>>().step_once();
+        assert_near(dispatch(&mut world), x + 1.0);
+        assert_near(dispatch(&mut world), x + 1.0);
+        assert!(world.read_resource::<PhysicsControl<f32>>().paused);
+
+        {
+            let mut control = world.write_resource::<PhysicsControl<f32>>();
+            control.resume();
+            control.time_scale = 0.5;
+        }
+        assert_near(dispatch(&mut world), x + 1.5);
+
+        // the unscaled timestep is kept by the nphysics World
+        let physics = world.read_resource::<Physics<f32>>();
+        assert_near(physics.mechanical_world.timestep(), 1.0 / 60.0);
+    }
+}
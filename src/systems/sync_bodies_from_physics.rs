@@ -1,8 +1,9 @@
 use std::marker::PhantomData;
 
-use specs::{Join, System, WriteExpect, WriteStorage};
+use specs::{Join, ReadStorage, System, WriteExpect, WriteStorage};
 
-use crate::{bodies::PhysicsBody, pose::Pose};
+use crate::{bodies::{PhysicsBody, PhysicsTimeScale},
+            pose::Pose};
 use nalgebra::RealField;
 use nphysics::object::DefaultBodySet;
 
@@ -18,20 +19,24 @@ where
 {
     type SystemData = (
         WriteStorage<'s, PhysicsBody<N>>,
+        ReadStorage<'s, PhysicsTimeScale<N>>,
         WriteExpect<'s, DefaultBodySet<N>>,
         WriteStorage<'s, P>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut physics_bodies, bodies, mut positions) = data;
+        let (mut physics_bodies, time_scales, bodies, mut positions) = data;
 
         // iterate over all PhysicBody components joined with their Poses
-        for (physics_body, position) in (&mut physics_bodies, &mut positions).join() {
+        for (physics_body, time_scale, position) in
+            (&mut physics_bodies, time_scales.maybe(), &mut positions).join()
+        {
             // if a RigidBody exists in the nphysics World we fetch it and update the
             // Pose component accordingly
             if let Some(rigid_body) = bodies.rigid_body(physics_body.handle.unwrap()) {
                 position.set_isometry(*rigid_body.position());
-                physics_body.update_from_physics_world(rigid_body);
+                physics_body
+                    .update_from_physics_world(rigid_body, PhysicsTimeScale::of(time_scale));
             }
         }
     }
@@ -1,11 +1,20 @@
 use std::marker::PhantomData;
 
-use specs::{storage::ComponentEvent, world::Index, BitSet, Join, ReadStorage, ReaderId, Resources,
-            System, SystemData, WriteExpect, WriteStorage};
+use specs::{storage::ComponentEvent, world::Index, BitSet, Entities, Join, Read, ReadStorage,
+            ReaderId, Resources, System, SystemData, Write, WriteExpect, WriteStorage};
 
-use crate::{bodies::PhysicsBody, pose::Pose, Physics, PhysicsWorld};
+use crate::{bodies::{PhysicsBody, PhysicsTimeScale},
+            colliders::{collider_user_data, PhysicsCollider, PhysicsColliders},
+            events::{BodyStatusChanged, BodyStatusEvents},
+            layers::CollisionLayers,
+            parameters::{PhysicsControl, TimeStep},
+            pose::Pose,
+            Physics, PhysicsWorld};
 use nalgebra::RealField;
-use nphysics::object::DefaultBodySet;
+use nphysics::math::{Force, ForceType, Inertia, Point};
+use nphysics::object::{Body, BodyPart, BodyStatus, Collider, DefaultBodyHandle, DefaultBodySet,
+                       DefaultColliderHandle, DefaultColliderSet};
+use nphysics::volumetric::Volumetric;
 
 use super::iterate_component_events;
 
@@ -14,6 +23,7 @@ use super::iterate_component_events;
 pub struct SyncBodiesToPhysicsSystem<N, P> {
     positions_reader_id: Option<ReaderId<ComponentEvent>>,
     physics_bodies_reader_id: Option<ReaderId<ComponentEvent>>,
+    time_scales_reader_id: Option<ReaderId<ComponentEvent>>,
     _phantom: PhantomData<(N, P)>,
 }
 
@@ -23,14 +33,43 @@ where
     P: Pose<N>,
 {
     type SystemData = (
+        Entities<'s>,
         ReadStorage<'s, P>,
+        ReadStorage<'s, PhysicsCollider<N>>,
+        ReadStorage<'s, PhysicsColliders<N>>,
+        ReadStorage<'s, PhysicsTimeScale<N>>,
+        Read<'s, CollisionLayers>,
+        Option<Read<'s, TimeStep<N>>>,
+        Option<Read<'s, PhysicsControl<N>>>,
+        Write<'s, BodyStatusEvents>,
         PhysicsWorld<'s, N>,
         WriteExpect<'s, DefaultBodySet<N>>,
+        WriteExpect<'s, DefaultColliderSet<N>>,
         WriteStorage<'s, PhysicsBody<N>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (positions, mut physics, mut bodies, mut physics_bodies) = data;
+        let (
+            entities,
+            positions,
+            physics_colliders,
+            physics_collider_lists,
+            time_scales,
+            collision_layers,
+            time_step,
+            control,
+            mut body_status_events,
+            mut physics,
+            mut bodies,
+            mut colliders,
+            mut physics_bodies,
+        ) = data;
+
+        // kinematic velocities are inferred over the upcoming step, so prefer the
+        // TimeStep resource over the worlds current timestep if it exists and scale
+        // it just like the PhysicsStepperSystem does
+        let time_step = time_step.map_or_else(|| physics.timestep(), |time_step| time_step.0)
+            * control.map_or_else(N::one, |control| control.effective_time_scale());
 
         // collect all ComponentEvents for the Pose storage
         let (inserted_positions, modified_positions, removed_positions) =
@@ -43,9 +82,14 @@ where
                 self.physics_bodies_reader_id.as_mut().unwrap(),
             );
 
+        // collect all ComponentEvents for the PhysicsTimeScale storage
+        let (inserted_time_scales, modified_time_scales, removed_time_scales) =
+            iterate_component_events(&time_scales, self.time_scales_reader_id.as_mut().unwrap());
+
         // iterate over PhysicsBody and Pose components with an id/Index that
         // exists in either of the collected ComponentEvent BitSets
-        for (position, mut physics_body, id) in (
+        for (entity, position, mut physics_body, id) in (
+            &entities,
             &positions,
             &mut physics_bodies,
             &inserted_positions
@@ -57,12 +101,15 @@ where
         )
             .join()
         {
+            let time_scale = PhysicsTimeScale::of(time_scales.get(entity));
+
             // handle inserted events
             if inserted_positions.contains(id) || inserted_physics_bodies.contains(id) {
                 debug!("Inserted PhysicsBody with id: {}", id);
                 add_rigid_body::<N, P>(
                     id,
                     &position,
+                    time_scale,
                     &mut physics,
                     &mut physics_body,
                     &mut *bodies,
@@ -72,14 +119,85 @@ where
             // handle modified events
             if modified_positions.contains(id) || modified_physics_bodies.contains(id) {
                 debug!("Modified PhysicsBody with id: {}", id);
+                let prev_status = physics_body
+                    .handle
+                    .and_then(|handle| bodies.rigid_body(handle))
+                    .map(|rigid_body| rigid_body.status());
+
                 update_rigid_body::<N, P>(
                     id,
                     &position,
+                    time_step,
+                    time_scale,
                     &mut physics_body,
                     &mut *bodies,
                     &modified_positions,
                     &modified_physics_bodies,
                 );
+
+                // the BodyStatus was modified, transition the body and its colliders
+                match prev_status {
+                    Some(prev_status) if prev_status != physics_body.body_status => {
+                        let new_status = physics_body.body_status;
+                        let body_colliders = physics
+                            .geometric_world
+                            .body_colliders(physics_body.handle.unwrap())
+                            .unwrap_or(&[]);
+
+                        transition_body_status::<N>(
+                            prev_status,
+                            &mut physics_body,
+                            body_colliders,
+                            &mut *bodies,
+                            &*colliders,
+                        );
+
+                        // colliders only have to be touched when entering or leaving the
+                        // disabled state
+                        if prev_status == BodyStatus::Disabled || new_status == BodyStatus::Disabled
+                        {
+                            update_collision_groups::<N>(
+                                new_status,
+                                body_colliders,
+                                &mut *colliders,
+                                &physics_colliders,
+                                &physics_collider_lists,
+                                &collision_layers,
+                                &entities,
+                            );
+                        }
+
+                        body_status_events.single_write(BodyStatusChanged {
+                            entity,
+                            prev_status,
+                            new_status,
+                        });
+                    }
+                    _ => {}
+                }
+
+                // CCD is enabled per collider in nphysics, so toggling it on the body is
+                // applied to all of its colliders
+                let handle = physics_body.handle.unwrap();
+                if physics.ccd_bodies.contains(&handle) != physics_body.ccd_enabled {
+                    match physics_body.ccd_enabled {
+                        true => physics.ccd_bodies.insert(handle),
+                        false => physics.ccd_bodies.remove(&handle),
+                    };
+
+                    let body_colliders = physics
+                        .geometric_world
+                        .body_colliders(handle)
+                        .unwrap_or(&[]);
+                    update_ccd::<N>(
+                        physics_body.ccd_enabled,
+                        body_colliders,
+                        &mut *colliders,
+                        &physics_colliders,
+                        &physics_collider_lists,
+                        &entities,
+                    );
+                }
             }
 
             // handle removed events
@@ -88,6 +206,46 @@ where
                 remove_rigid_body::<N, P>(id, &mut physics, &mut *bodies);
             }
         }
+
+        // a PhysicsTimeScale was inserted, modified or removed, rescale the velocity of
+        // the body; PhysicsBody::velocity still holds the unscaled one
+        for (physics_body, time_scale, _) in (
+            &physics_bodies,
+            time_scales.maybe(),
+            &inserted_time_scales | &modified_time_scales | &removed_time_scales,
+        )
+            .join()
+        {
+            if let Some(rigid_body) = physics_body
+                .handle
+                .and_then(|handle| bodies.rigid_body_mut(handle))
+            {
+                rigid_body.set_velocity(physics_body.velocity * PhysicsTimeScale::of(time_scale));
+            }
+        }
+
+        // nphysics applies the same gravity to all bodies, so make up for the
+        // difference of time scaled bodies with an additional acceleration
+        let gravity = *physics.gravity();
+        for (physics_body, time_scale) in (&physics_bodies, &time_scales).join() {
+            let time_scale = PhysicsTimeScale::of(Some(time_scale));
+            if !physics_body.gravity_enabled || time_scale == N::one() {
+                continue;
+            }
+
+            if let Some(rigid_body) = physics_body
+                .handle
+                .and_then(|handle| bodies.rigid_body_mut(handle))
+            {
+                let acceleration = gravity * (time_scale * time_scale - N::one());
+                rigid_body.apply_force(
+                    0,
+                    &Force::linear(acceleration),
+                    ForceType::AccelerationChange,
+                    false,
+                );
+            }
+        }
     }
 
     fn setup(&mut self, res: &mut Resources) {
@@ -100,6 +258,10 @@ where
         // register reader id for the PhysicsBody storage
         let mut physics_body_storage: WriteStorage<PhysicsBody<N>> = SystemData::fetch(&res);
         self.physics_bodies_reader_id = Some(physics_body_storage.register_reader());
+
+        // register reader id for the PhysicsTimeScale storage
+        let mut time_scale_storage: WriteStorage<PhysicsTimeScale<N>> = SystemData::fetch(&res);
+        self.time_scales_reader_id = Some(time_scale_storage.register_reader());
     }
 }
 
@@ -108,6 +270,7 @@ impl<N, P> Default for SyncBodiesToPhysicsSystem<N, P> {
         Self {
             positions_reader_id: None,
             physics_bodies_reader_id: None,
+            time_scales_reader_id: None,
             _phantom: PhantomData,
         }
     }
@@ -116,6 +279,7 @@ impl<N, P> Default for SyncBodiesToPhysicsSystem<N, P> {
 fn add_rigid_body<N, P>(
     id: Index,
     position: &P,
+    time_scale: N,
     physics: &mut Physics<N>,
     physics_body: &mut PhysicsBody<N>,
     bodies: &mut DefaultBodySet<N>,
@@ -134,7 +298,7 @@ fn add_rigid_body<N, P>(
     // create a new RigidBody in the PhysicsWorld and store its
     // handle for later usage
     let body = physics_body
-        .to_rigid_body_desc()
+        .to_rigid_body_desc(time_scale)
         .position(position.isometry())
         .user_data(id)
         .build();
@@ -142,6 +306,9 @@ fn add_rigid_body<N, P>(
 
     physics_body.handle = Some(handle);
     physics.body_handles.insert(id, handle);
+    if physics_body.ccd_enabled {
+        physics.ccd_bodies.insert(handle);
+    }
 
     info!(
         "Inserted rigid body to world with values: {:?}",
@@ -149,9 +316,12 @@ fn add_rigid_body<N, P>(
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_rigid_body<N, P>(
     id: Index,
     position: &P,
+    time_step: N,
+    time_scale: N,
     physics_body: &mut PhysicsBody<N>,
     bodies: &mut DefaultBodySet<N>,
     modified_positions: &BitSet,
@@ -163,12 +333,18 @@ fn update_rigid_body<N, P>(
     if let Some(rigid_body) = bodies.rigid_body_mut(physics_body.handle.unwrap()) {
         // the PhysicsBody was modified, update everything but the position
         if modified_physics_bodies.contains(id) {
-            physics_body.apply_to_physics_world(rigid_body);
+            physics_body.apply_to_physics_world(rigid_body, time_scale);
         }
 
-        // the Pose was modified, update the position directly
+        // the Pose was modified, update the position directly or drive kinematic
+        // bodies towards it
         if modified_positions.contains(id) {
-            rigid_body.set_position(position.isometry());
+            physics_body.apply_pose_to_physics_world(
+                rigid_body,
+                position.isometry(),
+                time_step,
+                time_scale,
+            );
         }
 
         trace!(
@@ -178,6 +354,149 @@ fn update_rigid_body<N, P>(
     }
 }
 
+/// Handles the side effects of a `BodyStatus` change on the body itself that
+/// nphysics does not take care of: bodies becoming dynamic have their mass
+/// properties recomputed from their colliders and bodies are woken up.
+fn transition_body_status<N>(
+    prev_status: BodyStatus,
+    physics_body: &mut PhysicsBody<N>,
+    body_colliders: &[DefaultColliderHandle],
+    bodies: &mut DefaultBodySet<N>,
+    colliders: &DefaultColliderSet<N>,
+) where
+    N: RealField,
+{
+    let new_status = physics_body.body_status;
+
+    if let Some(rigid_body) = bodies.rigid_body_mut(physics_body.handle.unwrap()) {
+        // static and kinematic bodies usually carry no meaningful mass properties,
+        // so derive the zero or degenerate ones from the attached colliders
+        // densities instead
+        let local_inertia = rigid_body.local_inertia();
+        let derive_mass = local_inertia.linear <= N::zero();
+        let derive_angular_inertia = local_inertia.angular == Inertia::<N>::zero().angular;
+        if new_status == BodyStatus::Dynamic && (derive_mass || derive_angular_inertia) {
+            let mut inertia = Inertia::<N>::zero();
+            let mut center_of_mass = Point::origin();
+            for collider in body_colliders
+                .iter()
+                .filter_map(|handle| colliders.get(*handle))
+                .filter(|collider| !collider.density().is_zero())
+            {
+                let (com, part_inertia) = collider
+                    .shape()
+                    .transformed_mass_properties(collider.density(), &collider.position_wrt_body());
+                let mass_sum = inertia.linear + part_inertia.linear;
+                if !mass_sum.is_zero() {
+                    center_of_mass = (center_of_mass * inertia.linear
+                        + com.coords * part_inertia.linear)
+                        / mass_sum;
+                }
+                inertia += part_inertia;
+            }
+
+            if !inertia.linear.is_zero() {
+                if derive_mass {
+                    rigid_body.set_mass(inertia.linear);
+                    rigid_body.set_local_center_of_mass(center_of_mass);
+                    physics_body.mass = inertia.linear;
+                    physics_body.local_center_of_mass = center_of_mass;
+                }
+                if derive_angular_inertia {
+                    rigid_body.set_angular_inertia(inertia.angular);
+                    physics_body.angular_inertia = inertia.angular;
+                }
+            }
+        }
+
+        if new_status != BodyStatus::Disabled {
+            rigid_body.activate();
+        }
+    }
+
+    info!(
+        "Transitioned rigid body from {:?} to {:?}",
+        prev_status, new_status
+    );
+}
+
+/// Reapplies the `CollisionGroups` of all colliders attached to a body whose
+/// status changed, removing the colliders of disabled bodies from (or restoring
+/// them to) collision detection.
+fn update_collision_groups<N>(
+    body_status: BodyStatus,
+    body_colliders: &[DefaultColliderHandle],
+    colliders: &mut DefaultColliderSet<N>,
+    physics_colliders: &ReadStorage<PhysicsCollider<N>>,
+    physics_collider_lists: &ReadStorage<PhysicsColliders<N>>,
+    collision_layers: &CollisionLayers,
+    entities: &Entities,
+) where
+    N: RealField,
+{
+    for collider_handle in body_colliders {
+        if let Some(collider) = colliders.get_mut(*collider_handle) {
+            if let Some(physics_collider) = physics_collider_of(
+                collider,
+                physics_colliders,
+                physics_collider_lists,
+                entities,
+            ) {
+                collider.set_collision_groups(
+                    physics_collider.collision_groups_for(body_status, collision_layers),
+                );
+            }
+        }
+    }
+}
+
+/// Enables or disables CCD for all colliders attached to a body, except for
+/// colliders overriding it through `PhysicsCollider::ccd_enabled`.
+fn update_ccd<N>(
+    ccd_enabled: bool,
+    body_colliders: &[DefaultColliderHandle],
+    colliders: &mut DefaultColliderSet<N>,
+    physics_colliders: &ReadStorage<PhysicsCollider<N>>,
+    physics_collider_lists: &ReadStorage<PhysicsColliders<N>>,
+    entities: &Entities,
+) where
+    N: RealField,
+{
+    for collider_handle in body_colliders {
+        if let Some(collider) = colliders.get_mut(*collider_handle) {
+            let ccd_enabled = physics_collider_of(
+                collider,
+                physics_colliders,
+                physics_collider_lists,
+                entities,
+            )
+            .and_then(|physics_collider| physics_collider.ccd_enabled)
+            .unwrap_or(ccd_enabled);
+            collider.enable_ccd(ccd_enabled);
+        }
+    }
+}
+
+/// Looks up the `PhysicsCollider` a physics `Collider` was created from.
+fn physics_collider_of<'a, N>(
+    collider: &Collider<N, DefaultBodyHandle>,
+    physics_colliders: &'a ReadStorage<PhysicsCollider<N>>,
+    physics_collider_lists: &'a ReadStorage<PhysicsColliders<N>>,
+    entities: &Entities,
+) -> Option<&'a PhysicsCollider<N>>
+where
+    N: RealField,
+{
+    // colliders created from a PhysicsColliders component carry the index of
+    // their definition next to the entity id
+    match collider_user_data(collider)? {
+        (id, None) => physics_colliders.get(entities.entity(id)),
+        (id, Some(part)) => physics_collider_lists
+            .get(entities.entity(id))
+            .and_then(|physics_collider_list| physics_collider_list.get(part)),
+    }
+}
+
 fn remove_rigid_body<N, P>(id: Index, physics: &mut Physics<N>, bodies: &mut DefaultBodySet<N>)
 where
     N: RealField,
@@ -186,15 +505,25 @@ where
     if let Some(handle) = physics.body_handles.remove(&id) {
         // remove body if it still exists in the PhysicsWorld
         bodies.remove(handle);
+        physics.ccd_bodies.remove(&handle);
         info!("Removed rigid body from world with id: {}", id);
     }
 }
 
 #[cfg(all(test, feature = "physics3d"))]
 mod tests {
-    use crate::{systems::SyncBodiesToPhysicsSystem, Physics, PhysicsBodyBuilder, SimplePosition};
-    use nalgebra::Isometry3;
-    use nphysics::object::BodyStatus;
+    use crate::{bodies::{PhysicsBody, PhysicsTimeScale},
+                colliders::Shape,
+                events::BodyStatusEvents,
+                parameters::{PhysicsControl, TimeStep},
+                physics_dispatcher,
+                systems::SyncBodiesToPhysicsSystem,
+                Physics, PhysicsBodyBuilder, PhysicsColliderBuilder, SimplePosition};
+    use nalgebra::{Isometry3, Matrix3};
+    use nphysics::{algebra::Velocity3,
+                   force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
 
     use specs::{world::Builder, DispatcherBuilder, World};
 
@@ -208,6 +537,8 @@ mod tests {
                 &[],
             )
             .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
         dispatcher.setup(&mut world.res);
 
         // create an Entity with the PhysicsBody component and execute the dispatcher
@@ -223,6 +554,199 @@ mod tests {
         // fetch the Physics instance and check for new bodies
         let physics = world.read_resource::<Physics<f32>>();
         assert_eq!(physics.body_handles.len(), 1);
-        assert_eq!(physics.world.bodies().count(), 1);
+        assert_eq!(
+            world.read_resource::<DefaultBodySet<f32>>().iter().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn body_status_transitions() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+        let mut reader_id = world.write_resource::<BodyStatusEvents>().register_reader();
+
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .angular_inertia(Matrix3::identity())
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 })
+                    .density(1.0)
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&mut world.res);
+        let angular_inertia = world
+            .read_storage::<PhysicsBody<f32>>()
+            .get(entity)
+            .unwrap()
+            .angular_inertia;
+
+        for (prev_status, new_status) in &[
+            (BodyStatus::Dynamic, BodyStatus::Static),
+            (BodyStatus::Static, BodyStatus::Disabled),
+            (BodyStatus::Disabled, BodyStatus::Dynamic),
+        ] {
+            {
+                let mut physics_bodies = world.write_storage::<PhysicsBody<f32>>();
+                let physics_body = physics_bodies.get_mut(entity).unwrap();
+                physics_body.body_status = *new_status;
+                // drop the mass of the body right before it becomes dynamic again
+                if *new_status == BodyStatus::Dynamic {
+                    physics_body.mass = 0.0;
+                }
+            }
+            dispatcher.dispatch(&mut world.res);
+
+            // exactly one event is emitted per transition
+            let events = world.read_resource::<BodyStatusEvents>();
+            let events: Vec<_> = events.read(&mut reader_id).collect();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].entity, entity);
+            assert_eq!(events[0].prev_status, *prev_status);
+            assert_eq!(events[0].new_status, *new_status);
+
+            // the colliders of disabled bodies are excluded from collision detection
+            let physics = world.read_resource::<Physics<f32>>();
+            let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+            let collider = colliders
+                .get(physics.collider_handle(entity.id(), None).unwrap())
+                .unwrap();
+            assert_eq!(
+                collider.collision_groups().is_member_of(0),
+                *new_status != BodyStatus::Disabled
+            );
+        }
+
+        // only the missing mass is derived from the collider, the valid angular
+        // inertia is kept
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let physics_body = physics_bodies.get(entity).unwrap();
+        let ball_mass = 4.0 / 3.0 * std::f32::consts::PI * 0.5f32.powi(3);
+        assert!((physics_body.mass - ball_mass).abs() < 1.0e-4);
+        assert_eq!(physics_body.angular_inertia, angular_inertia);
+    }
+
+    #[test]
+    fn infer_kinematic_velocity() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(TimeStep(0.5f32));
+        dispatcher.setup(&mut world.res);
+
+        // create a kinematic PhysicsBody that opted into velocity inference
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Kinematic)
+                    .infer_kinematic_velocity(true)
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        // move the Pose and make sure the body is driven instead of teleported
+        world
+            .write_storage::<SimplePosition<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .0 = Isometry3::<f32>::translation(1.0, 0.0, 0.0);
+        dispatcher.dispatch(&mut world.res);
+
+        let velocities = |world: &World| {
+            let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+            let physics_body = physics_bodies.get(entity).unwrap();
+            let bodies = world.read_resource::<DefaultBodySet<f32>>();
+            let rigid_body = bodies.rigid_body(physics_body.handle.unwrap()).unwrap();
+            assert_eq!(rigid_body.position().translation.vector.x, 0.0);
+            (
+                physics_body.velocity.linear.x,
+                rigid_body.velocity().linear.x,
+            )
+        };
+        assert_eq!(velocities(&world), (2.0, 2.0));
+
+        // the scaled step covers less time, while the PhysicsTimeScale only
+        // slows down the rigid body
+        world.add_resource(PhysicsControl {
+            time_scale: 0.5f32,
+            ..PhysicsControl::default()
+        });
+        world
+            .write_storage::<PhysicsTimeScale<f32>>()
+            .insert(entity, PhysicsTimeScale(0.5f32))
+            .unwrap();
+        world
+            .write_storage::<SimplePosition<f32>>()
+            .get_mut(entity)
+            .unwrap();
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(velocities(&world), (4.0, 2.0));
+    }
+
+    #[test]
+    fn time_scale() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(60.0, 0.0, 0.0))
+                    .build(),
+            )
+            .with(PhysicsTimeScale(0.5f32))
+            .build();
+        let mut dispatch = |world: &mut World| {
+            dispatcher.dispatch(&mut world.res);
+            let positions = world.read_storage::<SimplePosition<f32>>();
+            positions.get(entity).unwrap().0.translation.vector.x
+        };
+
+        // the body moves at half speed, while its PhysicsBody keeps the full velocity
+        assert!((dispatch(&mut world) - 0.5).abs() < 1.0e-4);
+        assert!((dispatch(&mut world) - 1.0).abs() < 1.0e-4);
+        assert_eq!(
+            world
+                .read_storage::<PhysicsBody<f32>>()
+                .get(entity)
+                .unwrap()
+                .velocity
+                .linear
+                .x,
+            60.0
+        );
+
+        // removing the PhysicsTimeScale restores the original velocity
+        world
+            .write_storage::<PhysicsTimeScale<f32>>()
+            .remove(entity);
+        assert!((dispatch(&mut world) - 2.0).abs() < 1.0e-4);
     }
 }
@@ -1,19 +1,39 @@
-use std::marker::PhantomData;
+use std::{any::Any, collections::HashSet, marker::PhantomData};
 
-use specs::{storage::ComponentEvent, world::Index, Join, ReadStorage, ReaderId, Resources, System,
-            SystemData, WriteExpect, WriteStorage};
+use specs::{storage::ComponentEvent, world::Index, BitSet, Entities, Entity, Join, Read,
+            ReadExpect, ReadStorage, ReaderId, Resources, System, SystemData, Write, WriteExpect,
+            WriteStorage};
+use specs_hierarchy::{HierarchyEvent, HierarchySetupHandler};
 
-use crate::{colliders::PhysicsCollider, pose::Pose, Physics, PhysicsParent, PhysicsWorld};
+use crate::{colliders::{PhysicsCollider, PhysicsColliders, ShapeCache},
+            error::PhysicsError,
+            layers::CollisionLayers,
+            materials::PhysicsMaterials,
+            pose::Pose,
+            Physics, PhysicsHierarchy, PhysicsParent, PhysicsWorld};
 use nalgebra::RealField;
-use nphysics::object::{BodyPartHandle, ColliderDesc, DefaultColliderSet};
+use nphysics::math::Isometry;
+use nphysics::object::{BodyPartHandle, BodyStatus, Collider, ColliderDesc, DefaultBodyHandle,
+                       DefaultBodySet, DefaultColliderSet};
 
 use super::iterate_component_events;
 
 /// The `SyncCollidersToPhysicsSystem` handles the synchronisation of
-/// `PhysicsCollider` `Component`s into the physics `World`.
+/// `PhysicsCollider` and `PhysicsColliders` `Component`s into the physics
+/// `World`.
+///
+/// Colliders are attached to the body of their own `Entity` or, if it has
+/// none, to the body of its nearest ancestor in the `PhysicsHierarchy`.
+/// Re-parenting an `Entity` moves its colliders to the new body. nphysics can't
+/// re-attach, rescale or change the material of a `Collider` in place, so
+/// re-parented colliders, colliders of rescaled `Pose`s and colliders using a
+/// named material whose definition changed are replaced, which changes their
+/// handles.
 pub struct SyncCollidersToPhysicsSystem<N, P> {
+    hierarchy_reader_id: Option<ReaderId<HierarchyEvent>>,
     positions_reader_id: Option<ReaderId<ComponentEvent>>,
     physics_colliders_reader_id: Option<ReaderId<ComponentEvent>>,
+    physics_collider_lists_reader_id: Option<ReaderId<ComponentEvent>>,
     _phantom: PhantomData<(N, P)>,
 }
 
@@ -23,20 +43,82 @@ where
     P: Pose<N>,
 {
     type SystemData = (
+        Entities<'s>,
         ReadStorage<'s, P>,
-        ReadStorage<'s, PhysicsParent>,
+        Read<'s, PhysicsHierarchy, HierarchySetupHandler<PhysicsParent>>,
         PhysicsWorld<'s, N>,
+        ReadExpect<'s, DefaultBodySet<N>>,
         WriteExpect<'s, DefaultColliderSet<N>>,
+        Write<'s, ShapeCache<N>>,
+        Write<'s, CollisionLayers>,
+        Write<'s, PhysicsMaterials<N>>,
         WriteStorage<'s, PhysicsCollider<N>>,
+        WriteStorage<'s, PhysicsColliders<N>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (positions, parent_entities, mut physics, mut colliders, mut physics_colliders) = data;
+        let (
+            entities,
+            positions,
+            hierarchy,
+            mut physics,
+            bodies,
+            mut colliders,
+            mut shape_cache,
+            mut collision_layers,
+            mut materials,
+            mut physics_colliders,
+            mut physics_collider_lists,
+        ) = data;
 
         // collect all ComponentEvents for the Pose storage
-        let (inserted_positions, ..) =
+        let (inserted_positions, modified_positions, _) =
             iterate_component_events(&positions, self.positions_reader_id.as_mut().unwrap());
 
+        // collect all Entities whose position in the hierarchy changed, including all
+        // of their children, as their colliders might have to move to another body
+        let mut reparented = BitSet::new();
+        for event in hierarchy
+            .changed()
+            .read(self.hierarchy_reader_id.as_mut().unwrap())
+        {
+            match event {
+                HierarchyEvent::Modified(entity) => {
+                    debug!("Got hierarchy Modified event for entity: {:?}", entity);
+                    reparented.add(entity.id());
+                    reparented |= &hierarchy.all_children(*entity);
+                }
+                HierarchyEvent::Removed(entity) => {
+                    debug!("Got hierarchy Removed event for entity: {:?}", entity);
+                    reparented.add(entity.id());
+                }
+            }
+        }
+
+        // collect all Entities with colliders using a named material whose
+        // definition got replaced, as they have to be recreated
+        let modified_materials = materials.take_modified_materials();
+        let uses_modified_material = |physics_collider: &PhysicsCollider<N>| {
+            physics_collider
+                .material_name
+                .as_ref()
+                .map_or(false, |name| modified_materials.contains(name))
+        };
+        let mut rematerialized = BitSet::new();
+        let mut rematerialized_lists = BitSet::new();
+        if !modified_materials.is_empty() {
+            for (entity, physics_collider) in (&entities, &physics_colliders).join() {
+                if uses_modified_material(physics_collider) {
+                    rematerialized.add(entity.id());
+                }
+            }
+            for (entity, physics_collider_list) in (&entities, &physics_collider_lists).join() {
+                if physics_collider_list.iter().any(uses_modified_material) {
+                    rematerialized_lists.add(entity.id());
+                }
+            }
+        }
+
         // collect all ComponentEvents for the PhysicsCollider storage
         let (inserted_physics_colliders, modified_physics_colliders, removed_physics_colliders) =
             iterate_component_events(
@@ -46,25 +128,59 @@ where
 
         // iterate over PhysicsCollider and Pose components with an id/Index that
         // exists in either of the collected ComponentEvent BitSets
-        for (position, parent_entity, mut physics_collider, id) in (
+        for (entity, position, mut physics_collider, id) in (
+            &entities,
             &positions,
-            parent_entities.maybe(),
             &mut physics_colliders.restrict_mut(),
             &inserted_positions
+                | &modified_positions
                 | &inserted_physics_colliders
                 | &modified_physics_colliders
-                | &removed_physics_colliders,
+                | &removed_physics_colliders
+                | &reparented
+                | &rematerialized,
         )
             .join()
         {
+            let parent_body_handle = parent_body_handle(entity, &hierarchy, &physics);
+
+            // re-parented colliders are recreated if their nearest body changed
+            let moved = reparented.contains(id)
+                && attached_body(physics_collider.get_unchecked(), &*colliders)
+                    != parent_body_handle;
+
+            // the shape and offset of a Collider can't be scaled in place, so colliders
+            // are recreated whenever the scale of their Pose changes
+            let rescaled = modified_positions.contains(id)
+                && physics_collider.get_unchecked().scale != position.scale();
+
+            // replaced colliders aren't orphaned, so remove them up front
+            let replaced = moved || rescaled || rematerialized.contains(id);
+            if replaced {
+                debug!("Replacing PhysicsCollider with id: {}", id);
+                remove_collider(
+                    id,
+                    physics_collider.get_mut_unchecked(),
+                    &mut physics,
+                    &mut *colliders,
+                );
+            }
+
             // handle inserted events
-            if inserted_positions.contains(id) || inserted_physics_colliders.contains(id) {
+            if inserted_positions.contains(id)
+                || inserted_physics_colliders.contains(id)
+                || replaced
+            {
                 debug!("Inserted PhysicsCollider with id: {}", id);
                 add_collider::<N, P>(
                     id,
-                    parent_entity,
+                    parent_body_handle,
                     &position,
                     &mut physics,
+                    &*bodies,
+                    &*shape_cache,
+                    &*collision_layers,
+                    &*materials,
                     physics_collider.get_mut_unchecked(),
                     &mut *colliders,
                 );
@@ -72,13 +188,15 @@ where
 
             // handle modified events
             if modified_physics_colliders.contains(id) {
-                let physics_collider = physics_collider.get_unchecked();
-                let collider_handle = physics_collider.handle.unwrap();
-
-                colliders
-                    .get_mut(collider_handle)
-                    .unwrap()
-                    .set_collision_groups(physics_collider.collision_groups);
+                let physics_collider = physics_collider.get_mut_unchecked();
+                update_collider(
+                    physics_collider,
+                    &*bodies,
+                    &*shape_cache,
+                    &*collision_layers,
+                    &physics.ccd_bodies,
+                    &mut *colliders,
+                );
                 debug!(
                     "Updated collider with id {:?} with values: {:?}",
                     id, physics_collider
@@ -88,54 +206,209 @@ where
             // handle removed events
             if removed_physics_colliders.contains(id) {
                 debug!("Removed PhysicsCollider with id: {}", id);
-                if let Some(handle) = physics.collider_handles.remove(&id) {
-                    // we have to check if the collider still exists in the nphysics World before
-                    // attempting to delete it as removing a collider that does not exist anymore
-                    // causes the nphysics World to panic; colliders are implicitly removed when a
-                    // parent body is removed so this is actually a valid scenario
-                    if colliders.get(handle).is_some() {
-                        colliders.remove(handle);
-                    }
+                remove_collider(
+                    id,
+                    physics_collider.get_mut_unchecked(),
+                    &mut physics,
+                    &mut *colliders,
+                );
+            }
+        }
+
+        // collect all ComponentEvents for the PhysicsColliders storage
+        let (
+            inserted_physics_collider_lists,
+            modified_physics_collider_lists,
+            removed_physics_collider_lists,
+        ) = iterate_component_events(
+            &physics_collider_lists,
+            self.physics_collider_lists_reader_id.as_mut().unwrap(),
+        );
+
+        // handle removed events first; removed components can't be joined over and
+        // a component that got replaced within the same frame is re-added below
+        for id in (&removed_physics_collider_lists).join() {
+            debug!("Removed PhysicsColliders with id: {}", id);
+            remove_collider_list(id, &mut physics, &mut *colliders);
+        }
+
+        // iterate over PhysicsColliders and Pose components the same way
+        for (entity, position, mut physics_collider_list, id) in (
+            &entities,
+            &positions,
+            &mut physics_collider_lists.restrict_mut(),
+            &inserted_positions
+                | &modified_positions
+                | &inserted_physics_collider_lists
+                | &modified_physics_collider_lists
+                | &reparented
+                | &rematerialized_lists,
+        )
+            .join()
+        {
+            let physics_collider_list = physics_collider_list.get_mut_unchecked();
+            let parent_body_handle = parent_body_handle(entity, &hierarchy, &physics);
+
+            // re-parented colliders are recreated if their nearest body changed
+            let moved = reparented.contains(id)
+                && physics_collider_list
+                    .first()
+                    .and_then(|physics_collider| attached_body(physics_collider, &*colliders))
+                    != parent_body_handle;
+
+            let rescaled = modified_positions.contains(id)
+                && physics_collider_list
+                    .first()
+                    .map_or(false, |physics_collider| {
+                        physics_collider.scale != position.scale()
+                    });
+
+            // changing the amount of PhysicsColliders cannot be mapped onto the
+            // existing colliders, so they are recreated just like on insertion
+            let resized = modified_physics_collider_lists.contains(id)
+                && physics
+                    .collider_list_handles
+                    .get(&id)
+                    .map_or(true, |handles| handles.len() != physics_collider_list.len());
+
+            // handle inserted events
+            if inserted_positions.contains(id)
+                || inserted_physics_collider_lists.contains(id)
+                || resized
+                || moved
+                || rescaled
+                || rematerialized_lists.contains(id)
+            {
+                debug!("Inserted PhysicsColliders with id: {}", id);
+                add_collider_list::<N, P>(
+                    id,
+                    parent_body_handle,
+                    &position,
+                    &mut physics,
+                    &*bodies,
+                    &*shape_cache,
+                    &*collision_layers,
+                    &*materials,
+                    physics_collider_list,
+                    &mut *colliders,
+                );
+            } else if modified_physics_collider_lists.contains(id) {
+                // handle modified events
+                for physics_collider in physics_collider_list.iter_mut() {
+                    update_collider(
+                        physics_collider,
+                        &*bodies,
+                        &*shape_cache,
+                        &*collision_layers,
+                        &physics.ccd_bodies,
+                        &mut *colliders,
+                    );
                 }
+                debug!(
+                    "Updated colliders with id {:?} with values: {:?}",
+                    id, physics_collider_list
+                );
             }
         }
 
+        // swap the shapes of all colliders whose shared definition got replaced
+        let replaced = shape_cache.take_replaced();
+        if !replaced.is_empty() {
+            for physics_collider in (&physics_colliders).join().chain(
+                (&physics_collider_lists)
+                    .join()
+                    .flat_map(|physics_collider_list| physics_collider_list.iter()),
+            ) {
+                if physics_collider.shape.shares_any(&replaced) {
+                    update_collider_shape(physics_collider, &*shape_cache, &mut *colliders);
+                }
+            }
+        }
+
+        // reapply the CollisionGroups of all layered colliders if the rules between
+        // layers changed
+        if collision_layers.take_modified() {
+            for physics_collider in (&physics_colliders).join().chain(
+                (&physics_collider_lists)
+                    .join()
+                    .flat_map(|physics_collider_list| physics_collider_list.iter()),
+            ) {
+                if physics_collider.collision_layer.is_some() {
+                    update_collision_groups(
+                        physics_collider,
+                        &*bodies,
+                        &*collision_layers,
+                        &mut *colliders,
+                    );
+                }
+            }
+        }
+
+        // sync the coefficients between pairs of materials into the lookup table
+        // used by the contact model
+        if materials.take_modified_coefficients() {
+            physics.mechanical_world.material_coefficients = materials.coefficients_table();
+        }
+
         // Drain update triggers caused by inserts
         let event_iter = physics_colliders
             .channel()
             .read(self.physics_colliders_reader_id.as_mut().unwrap());
         for _ in event_iter {}
+
+        let event_iter = physics_collider_lists
+            .channel()
+            .read(self.physics_collider_lists_reader_id.as_mut().unwrap());
+        for _ in event_iter {}
     }
 
     fn setup(&mut self, res: &mut Resources) {
+        // the PhysicsHierarchy is set up from the PhysicsParent storage
+        ReadStorage::<PhysicsParent>::setup(res);
         Self::SystemData::setup(res);
 
+        // register reader id for the PhysicsHierarchy
+        self.hierarchy_reader_id = Some(res.fetch_mut::<PhysicsHierarchy>().track());
+
         // register reader id for the Pose storage
         let mut position_storage: WriteStorage<P> = SystemData::fetch(&res);
         self.positions_reader_id = Some(position_storage.register_reader());
 
-        // register reader id for the PhysicsBody storage
+        // register reader id for the PhysicsCollider storage
         let mut physics_collider_storage: WriteStorage<PhysicsCollider<N>> =
             SystemData::fetch(&res);
         self.physics_colliders_reader_id = Some(physics_collider_storage.register_reader());
+
+        // register reader id for the PhysicsColliders storage
+        let mut physics_collider_list_storage: WriteStorage<PhysicsColliders<N>> =
+            SystemData::fetch(&res);
+        self.physics_collider_lists_reader_id =
+            Some(physics_collider_list_storage.register_reader());
     }
 }
 
 impl<N, P> Default for SyncCollidersToPhysicsSystem<N, P> {
     fn default() -> Self {
         Self {
+            hierarchy_reader_id: None,
             positions_reader_id: None,
             physics_colliders_reader_id: None,
+            physics_collider_lists_reader_id: None,
             _phantom: PhantomData,
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn add_collider<N, P>(
     id: Index,
-    parent_entity: Option<&PhysicsParent>,
+    parent_body_handle: Option<DefaultBodyHandle>,
     position: &P,
     physics: &mut Physics<N>,
+    bodies: &DefaultBodySet<N>,
+    shape_cache: &ShapeCache<N>,
+    collision_layers: &CollisionLayers,
+    materials: &PhysicsMaterials<N>,
     physics_collider: &mut PhysicsCollider<N>,
     colliders: &mut DefaultColliderSet<N>,
 ) where
@@ -143,41 +416,42 @@ fn add_collider<N, P>(
     P: Pose<N>,
 {
     // remove already existing colliders for this inserted event
-    if let Some(handle) = physics.collider_handles.remove(&id) {
+    if let Some(handle) = physics.collider_handles.get(&id) {
         warn!("Removing orphaned collider handle: {:?}", handle);
-        colliders.remove(handle);
+        remove_collider(id, physics_collider, physics, colliders);
     }
 
     // Don't allow mis-matched colliders and bodies
-    let parent_part_handle = match (physics.body_handles.get(&id), parent_entity) {
-        (Some(x), _) => x,
-        (_, Some(x)) if physics.body_handles.contains_key(&x.entity.id()) => {
-            physics.body_handles.get(&x.entity.id()).unwrap()
-        }
-        _ => {
+    let parent_body_handle = match parent_body_handle {
+        Some(handle) => handle,
+        None => {
             error!("Attempted to add a collider to nonexistent body! Skipping...");
             return;
         }
     };
 
-    // Create the Collider and fetch its handle. We know the body part handle will
-    // always have index 0 due to ecs requirement.
-    let mut collider = ColliderDesc::new(physics_collider.shape_handle())
-        .position(position.isometry() * physics_collider.offset_from_parent)
-        .density(physics_collider.density)
-        .margin(physics_collider.margin)
-        .collision_groups(physics_collider.collision_groups)
-        .linear_prediction(physics_collider.linear_prediction)
-        .angular_prediction(physics_collider.angular_prediction)
-        .sensor(physics_collider.sensor)
-        .user_data(id);
-
-    if let Some(material) = &physics_collider.material {
-        collider = collider.material(material.clone());
-    }
-
-    let collider = collider.build(BodyPartHandle(*parent_part_handle, 0));
-
+    // invalid shapes are skipped instead of bringing down the whole simulation
+    physics_collider.scale = position.scale();
+    let collider = match build_collider(
+        physics_collider,
+        position,
+        bodies,
+        shape_cache,
+        collision_layers,
+        materials,
+        physics.ccd_bodies.contains(&parent_body_handle),
+        parent_body_handle,
+        id,
+    ) {
+        Ok(collider) => collider,
+        Err(err) => {
+            error!(
+                "Failed to create collider of entity {}: {}. Skipping...",
+                id, err
+            );
+            return;
+        }
+    };
     let handle = colliders.insert(collider);
 
     physics_collider.handle = Some(handle);
@@ -189,24 +463,317 @@ fn add_collider<N, P>(
     );
 }
 
+#[allow(clippy::too_many_arguments)]
+fn add_collider_list<N, P>(
+    id: Index,
+    parent_body_handle: Option<DefaultBodyHandle>,
+    position: &P,
+    physics: &mut Physics<N>,
+    bodies: &DefaultBodySet<N>,
+    shape_cache: &ShapeCache<N>,
+    collision_layers: &CollisionLayers,
+    materials: &PhysicsMaterials<N>,
+    physics_collider_list: &mut PhysicsColliders<N>,
+    colliders: &mut DefaultColliderSet<N>,
+) where
+    N: RealField,
+    P: Pose<N>,
+{
+    // remove already existing colliders for this inserted event, replaced
+    // colliders are recreated the same way
+    remove_collider_list(id, physics, colliders);
+    for physics_collider in physics_collider_list.iter_mut() {
+        physics_collider.handle = None;
+    }
+
+    // Don't allow mis-matched colliders and bodies
+    let parent_body_handle = match parent_body_handle {
+        Some(handle) => handle,
+        None => {
+            error!("Attempted to add colliders to nonexistent body! Skipping...");
+            return;
+        }
+    };
+
+    // every Collider stores the index of its definition next to the entity id, so
+    // events are able to report which one was involved; all of them are built
+    // upfront so a single invalid shape doesn't leave the list half inserted
+    for physics_collider in physics_collider_list.iter_mut() {
+        physics_collider.scale = position.scale();
+    }
+    let ccd_enabled = physics.ccd_bodies.contains(&parent_body_handle);
+    let built = physics_collider_list
+        .iter()
+        .enumerate()
+        .map(|(part, physics_collider)| {
+            build_collider(
+                physics_collider,
+                position,
+                bodies,
+                shape_cache,
+                collision_layers,
+                materials,
+                ccd_enabled,
+                parent_body_handle,
+                (id, part),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>();
+    let built = match built {
+        Ok(built) => built,
+        Err(err) => {
+            error!(
+                "Failed to create colliders of entity {}: {}. Skipping...",
+                id, err
+            );
+            return;
+        }
+    };
+
+    let mut handles = Vec::with_capacity(built.len());
+    for (physics_collider, collider) in physics_collider_list.iter_mut().zip(built) {
+        let handle = colliders.insert(collider);
+
+        physics_collider.handle = Some(handle);
+        handles.push(handle);
+    }
+    physics.collider_list_handles.insert(id, handles);
+
+    info!(
+        "Inserted colliders to world with values: {:?}",
+        physics_collider_list
+    );
+}
+
+fn update_collider<N>(
+    physics_collider: &mut PhysicsCollider<N>,
+    bodies: &DefaultBodySet<N>,
+    shape_cache: &ShapeCache<N>,
+    collision_layers: &CollisionLayers,
+    ccd_bodies: &HashSet<DefaultBodyHandle>,
+    colliders: &mut DefaultColliderSet<N>,
+) where
+    N: RealField,
+{
+    // the shape might have been replaced or changed in place, e.g. a deformed
+    // heightfield, so it is swapped out
+    update_collider_shape(physics_collider, shape_cache, colliders);
+
+    // toggling `enabled` merely swaps the collision groups, so the Collider and its
+    // ShapeHandle are kept intact
+    update_collision_groups(physics_collider, bodies, collision_layers, colliders);
+
+    if let Some(collider) = physics_collider
+        .handle
+        .and_then(|handle| colliders.get_mut(handle))
+    {
+        let ccd_enabled = physics_collider
+            .ccd_enabled
+            .unwrap_or_else(|| ccd_bodies.contains(&collider.body()));
+        collider.enable_ccd(ccd_enabled);
+    }
+}
+
+fn update_collision_groups<N>(
+    physics_collider: &PhysicsCollider<N>,
+    bodies: &DefaultBodySet<N>,
+    collision_layers: &CollisionLayers,
+    colliders: &mut DefaultColliderSet<N>,
+) where
+    N: RealField,
+{
+    if let Some(collider) = physics_collider
+        .handle
+        .and_then(|handle| colliders.get_mut(handle))
+    {
+        let body_status = body_status(bodies, collider.body());
+        collider.set_collision_groups(
+            physics_collider.collision_groups_for(body_status, collision_layers),
+        );
+    }
+}
+
+fn update_collider_shape<N>(
+    physics_collider: &PhysicsCollider<N>,
+    shape_cache: &ShapeCache<N>,
+    colliders: &mut DefaultColliderSet<N>,
+) where
+    N: RealField,
+{
+    if let Some(collider) = physics_collider
+        .handle
+        .and_then(|handle| colliders.get_mut(handle))
+    {
+        match physics_collider.shape_handle(shape_cache) {
+            Ok(shape_handle) => collider.set_shape(shape_handle),
+            Err(err) => error!("Failed to update shape of collider: {}", err),
+        }
+    }
+}
+
+/// Removes the physics `Collider` of a `PhysicsCollider`, e.g. before replacing
+/// it, and resets its handle.
+fn remove_collider<N>(
+    id: Index,
+    physics_collider: &mut PhysicsCollider<N>,
+    physics: &mut Physics<N>,
+    colliders: &mut DefaultColliderSet<N>,
+) where
+    N: RealField,
+{
+    if let Some(handle) = physics.collider_handles.remove(&id) {
+        // we have to check if the collider still exists in the nphysics World before
+        // attempting to delete it as removing a collider that does not exist anymore
+        // causes the nphysics World to panic; colliders are implicitly removed when a
+        // parent body is removed so this is actually a valid scenario
+        if colliders.get(handle).is_some() {
+            colliders.remove(handle);
+        }
+    }
+    physics_collider.handle = None;
+}
+
+fn remove_collider_list<N>(
+    id: Index,
+    physics: &mut Physics<N>,
+    colliders: &mut DefaultColliderSet<N>,
+) where
+    N: RealField,
+{
+    if let Some(handles) = physics.collider_list_handles.remove(&id) {
+        // colliders are implicitly removed alongside their parent body, so make
+        // sure they still exist before attempting to delete them
+        for handle in handles {
+            if colliders.get(handle).is_some() {
+                colliders.remove(handle);
+            }
+        }
+    }
+}
+
+/// Resolves the handle of the body the colliders of the given `Entity` are
+/// attached to; either its own body or the one of its nearest ancestor in the
+/// `PhysicsHierarchy` that has a body.
+fn parent_body_handle<N: RealField>(
+    entity: Entity,
+    hierarchy: &PhysicsHierarchy,
+    physics: &Physics<N>,
+) -> Option<DefaultBodyHandle> {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if let Some(handle) = physics.body_handles.get(&entity.id()) {
+            return Some(*handle);
+        }
+        current = hierarchy.parent(entity);
+    }
+
+    None
+}
+
+/// Returns the handle of the body the physics `Collider` of a `PhysicsCollider`
+/// is currently attached to, if it exists.
+fn attached_body<N: RealField>(
+    physics_collider: &PhysicsCollider<N>,
+    colliders: &DefaultColliderSet<N>,
+) -> Option<DefaultBodyHandle> {
+    physics_collider
+        .handle
+        .and_then(|handle| colliders.get(handle))
+        .map(|collider| collider.body())
+}
+
+/// Creates the physics `Collider` described by a `PhysicsCollider`. We know the
+/// body part handle will always have index 0 due to ecs requirement. Fails if
+/// the `Shape` of the `PhysicsCollider` is invalid or its material is unknown.
+/// CCD is enabled if the body has it enabled, unless the `PhysicsCollider`
+/// overrides it.
+#[allow(clippy::too_many_arguments)]
+fn build_collider<N, P, U>(
+    physics_collider: &PhysicsCollider<N>,
+    position: &P,
+    bodies: &DefaultBodySet<N>,
+    shape_cache: &ShapeCache<N>,
+    collision_layers: &CollisionLayers,
+    materials: &PhysicsMaterials<N>,
+    body_ccd_enabled: bool,
+    parent_body_handle: DefaultBodyHandle,
+    user_data: U,
+) -> Result<Collider<N, DefaultBodyHandle>, PhysicsError>
+where
+    N: RealField,
+    P: Pose<N>,
+    U: Any + Clone + Send + Sync,
+{
+    // the position of a Collider is relative to its body, which may belong to an
+    // ancestor of the Entity
+    let body_position = bodies
+        .get(parent_body_handle)
+        .and_then(|body| body.part(0))
+        .map_or_else(Isometry::identity, |part| part.position());
+    let mut collider = ColliderDesc::new(physics_collider.shape_handle(shape_cache)?)
+        .position(body_position.inverse() * position.isometry() * physics_collider.scaled_offset())
+        .density(physics_collider.density)
+        .margin(physics_collider.margin)
+        .collision_groups(
+            physics_collider
+                .collision_groups_for(body_status(bodies, parent_body_handle), collision_layers),
+        )
+        .linear_prediction(physics_collider.linear_prediction)
+        .angular_prediction(physics_collider.angular_prediction)
+        .sensor(physics_collider.sensor)
+        .ccd_enabled(physics_collider.ccd_enabled.unwrap_or(body_ccd_enabled))
+        .user_data(user_data);
+
+    if let Some(material) = physics_collider.material_handle(materials)? {
+        collider = collider.material(material);
+    }
+
+    Ok(collider.build(BodyPartHandle(parent_body_handle, 0)))
+}
+
+/// Returns the `BodyStatus` of the body with the given handle, defaulting to
+/// `BodyStatus::Dynamic` if it doesn't exist (anymore).
+fn body_status<N: RealField>(bodies: &DefaultBodySet<N>, handle: DefaultBodyHandle) -> BodyStatus {
+    bodies
+        .get(handle)
+        .map_or(BodyStatus::Dynamic, |body| body.status())
+}
+
 #[cfg(all(test, feature = "physics3d"))]
 mod tests {
-    use specs::{world::Builder, DispatcherBuilder, World};
+    use specs::{world::Builder, Component, DenseVecStorage, DispatcherBuilder, FlaggedStorage,
+                World};
+    use specs_hierarchy::HierarchySystem;
 
-    use crate::{colliders::Shape, systems::SyncCollidersToPhysicsSystem, Physics,
-                PhysicsColliderBuilder, SimplePosition};
-    use nalgebra::Isometry3;
+    use crate::{colliders::{PhysicsCollider, PhysicsColliders, Shape, ShapeCache},
+                layers::CollisionLayers,
+                materials::{PhysicsMaterial, PhysicsMaterials},
+                pose::Pose,
+                systems::{SyncBodiesToPhysicsSystem, SyncCollidersToPhysicsSystem},
+                Physics, PhysicsBodyBuilder, PhysicsColliderBuilder, PhysicsParent, SimplePosition};
+    use nalgebra::{DMatrix, Isometry3, Vector3};
+    use ncollide::{pipeline::CollisionGroups,
+                   shape::{Ball, Cuboid, HeightField}};
+    use nphysics::{material::BasicMaterial,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
 
     #[test]
     fn add_collider() {
         let mut world = World::new();
         let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
             .with(
                 SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
                 "sync_colliders_to_physics_system",
-                &[],
+                &["sync_bodies_to_physics_system"],
             )
             .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
         dispatcher.setup(&mut world.res);
 
         // create an Entity with the PhysicsCollider component and execute the
@@ -216,6 +783,7 @@ mod tests {
             .with(SimplePosition::<f32>(Isometry3::<f32>::translation(
                 1.0, 1.0, 1.0,
             )))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
             .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 5.0 }).build())
             .build();
         dispatcher.dispatch(&mut world.res);
@@ -223,6 +791,640 @@ mod tests {
         // fetch the Physics instance and check for new colliders
         let physics = world.read_resource::<Physics<f32>>();
         assert_eq!(physics.collider_handles.len(), 1);
-        assert_eq!(physics.world.colliders().count(), 1);
+        assert_eq!(
+            world
+                .read_resource::<DefaultColliderSet<f32>>()
+                .iter()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn disable_collider() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        let handle = world
+            .read_storage::<PhysicsCollider<f32>>()
+            .get(entity)
+            .unwrap()
+            .handle;
+
+        // disable the PhysicsCollider and make sure the Collider is kept but no
+        // longer interacts with anything
+        world
+            .write_storage::<PhysicsCollider<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .enabled = false;
+        dispatcher.dispatch(&mut world.res);
+
+        let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+        assert_eq!(physics_colliders.get(entity).unwrap().handle, handle);
+
+        let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+        let collider = colliders.get(handle.unwrap()).unwrap();
+        assert!(!collider
+            .collision_groups()
+            .can_interact_with_groups(&CollisionGroups::default()));
+    }
+
+    #[test]
+    fn skip_invalid_collider() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        // a Ball without radius is rejected instead of being passed to ncollide
+        assert!(Shape::Ball { radius: 0.0 }.handle().is_err());
+
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.0 }).build())
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+        assert!(physics_colliders.get(entity).unwrap().handle.is_none());
+
+        let physics = world.read_resource::<Physics<f32>>();
+        assert!(physics.collider_handles.get(&entity.id()).is_none());
+    }
+
+    #[test]
+    fn share_cached_shape() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        world
+            .write_resource::<ShapeCache<f32>>()
+            .insert("rock", Shape::Ball { radius: 1.0 })
+            .unwrap();
+
+        let entities = (0..2)
+            .map(|_| {
+                world
+                    .create_entity()
+                    .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+                    .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                    .with(PhysicsColliderBuilder::<f32>::from(Shape::Shared("rock".into())).build())
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        dispatcher.dispatch(&mut world.res);
+
+        // replacing the definition swaps the shape of all colliders using it
+        world
+            .write_resource::<ShapeCache<f32>>()
+            .insert("rock", Shape::Ball { radius: 2.0 })
+            .unwrap();
+        dispatcher.dispatch(&mut world.res);
+
+        let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+        let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+        for entity in entities {
+            let handle = physics_colliders.get(entity).unwrap().handle.unwrap();
+            let ball = colliders
+                .get(handle)
+                .unwrap()
+                .shape()
+                .as_shape::<Ball<f32>>()
+                .unwrap();
+            assert_eq!(ball.radius(), 2.0);
+        }
+    }
+
+    #[test]
+    fn deform_heightfield() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let shape = Shape::HeightField {
+            heights: DMatrix::from_element(3, 3, 1.0),
+            scale: Vector3::new(10.0, 1.0, 10.0),
+        };
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(PhysicsColliderBuilder::<f32>::from(shape).build())
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        let handle = world
+            .read_storage::<PhysicsCollider<f32>>()
+            .get(entity)
+            .unwrap()
+            .handle;
+
+        // dig a hole into the center, the Collider is kept but its shape is swapped
+        world
+            .write_storage::<PhysicsCollider<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .deform_heights(1..2, 1..2, |_, _, height| *height = 0.0)
+            .unwrap();
+        dispatcher.dispatch(&mut world.res);
+
+        {
+            let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+            assert_eq!(physics_colliders.get(entity).unwrap().handle, handle);
+
+            let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+            let heightfield = colliders
+                .get(handle.unwrap())
+                .unwrap()
+                .shape()
+                .as_shape::<HeightField<f32>>()
+                .unwrap();
+            assert_eq!(heightfield.heights()[(1, 1)], 0.0);
+        }
+
+        // replacing the shape swaps it as well
+        world
+            .write_storage::<PhysicsCollider<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .shape = Shape::Ball { radius: 2.0 };
+        dispatcher.dispatch(&mut world.res);
+
+        let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+        assert_eq!(physics_colliders.get(entity).unwrap().handle, handle);
+
+        let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+        let ball = colliders
+            .get(handle.unwrap())
+            .unwrap()
+            .shape()
+            .as_shape::<Ball<f32>>()
+            .unwrap();
+        assert_eq!(ball.radius(), 2.0);
+    }
+
+    #[test]
+    fn apply_collision_layers() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let mut layers = CollisionLayers::default();
+        layers.add_layer("player").unwrap();
+        layers.add_layer("terrain").unwrap();
+        world.add_resource(layers);
+
+        let mut create = |layer: &str| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .with(
+                    PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 })
+                        .collision_layer(layer)
+                        .build(),
+                )
+                .build()
+        };
+        let player = create("player");
+        let terrain = create("terrain");
+        dispatcher.dispatch(&mut world.res);
+
+        let can_interact = |world: &World| {
+            let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+            let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+            let groups = |entity| {
+                *colliders
+                    .get(physics_colliders.get(entity).unwrap().handle.unwrap())
+                    .unwrap()
+                    .collision_groups()
+            };
+            groups(player).can_interact_with_groups(&groups(terrain))
+        };
+        assert!(can_interact(&world));
+
+        // editing the rules reapplies the groups of existing colliders
+        world
+            .write_resource::<CollisionLayers>()
+            .set_collides("player", "terrain", false)
+            .unwrap();
+        dispatcher.dispatch(&mut world.res);
+        assert!(!can_interact(&world));
+    }
+
+    #[test]
+    fn apply_named_materials() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let mut materials = PhysicsMaterials::<f32>::default();
+        let ice = materials.add_material(
+            "ice",
+            PhysicsMaterial {
+                friction: 0.02,
+                ..PhysicsMaterial::default()
+            },
+        );
+        let rubber = materials.add_material("rubber", PhysicsMaterial::default());
+        materials.set_friction("ice", "rubber", Some(0.1)).unwrap();
+        world.add_resource(materials);
+
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 })
+                    .material_named("ice")
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        let friction = |world: &World| {
+            let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+            let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+            let collider = colliders
+                .get(physics_colliders.get(entity).unwrap().handle.unwrap())
+                .unwrap();
+            let material = collider
+                .material()
+                .downcast_ref::<BasicMaterial<f32>>()
+                .unwrap();
+            assert_eq!(material.id, Some(ice));
+            material.friction
+        };
+        assert_eq!(friction(&world), 0.02);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .materials_coefficients_table()
+                .friction_coefficient(ice, rubber),
+            Some(0.1)
+        );
+
+        // replacing the definition recreates the existing collider
+        world
+            .write_resource::<PhysicsMaterials<f32>>()
+            .add_material(
+                "ice",
+                PhysicsMaterial {
+                    friction: 0.05,
+                    ..PhysicsMaterial::default()
+                },
+            );
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(friction(&world), 0.05);
+    }
+
+    /// A `Pose` with a scale, like the `Transform` of amethyst.
+    struct ScaledPosition(Isometry3<f32>, Vector3<f32>);
+
+    impl Component for ScaledPosition {
+        type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+    }
+
+    impl Pose<f32> for ScaledPosition {
+        fn isometry(&self) -> Isometry3<f32> {
+            self.0
+        }
+
+        fn set_isometry(&mut self, isometry: Isometry3<f32>) -> &mut Self {
+            self.0 = isometry;
+            self
+        }
+
+        fn scale(&self) -> Vector3<f32> {
+            self.1
+        }
+    }
+
+    #[test]
+    fn scale_collider() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, ScaledPosition>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, ScaledPosition>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let entity = world
+            .create_entity()
+            .with(ScaledPosition(
+                Isometry3::identity(),
+                Vector3::new(2.0, 1.0, 3.0),
+            ))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(1.0, 1.0, 1.0),
+                })
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        let half_extents = |world: &World| {
+            let handle = world
+                .read_storage::<PhysicsCollider<f32>>()
+                .get(entity)
+                .unwrap()
+                .handle
+                .unwrap();
+            let colliders = world.read_resource::<DefaultColliderSet<f32>>();
+            *colliders
+                .get(handle)
+                .unwrap()
+                .shape()
+                .as_shape::<Cuboid<f32>>()
+                .unwrap()
+                .half_extents()
+        };
+        assert_eq!(half_extents(&world), Vector3::new(2.0, 1.0, 3.0));
+
+        // changing the scale rebuilds the shape
+        world
+            .write_storage::<ScaledPosition>()
+            .get_mut(entity)
+            .unwrap()
+            .1 = Vector3::new(1.0, 4.0, 1.0);
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(half_extents(&world), Vector3::new(1.0, 4.0, 1.0));
+
+        // a Ball can't be stretched, so the largest factor is used
+        let ball = Shape::Ball { radius: 1.0 }
+            .scaled(&Vector3::new(2.0, 1.0, 3.0))
+            .unwrap();
+        match ball {
+            Shape::Ball { radius } => assert_eq!(radius, 3.0),
+            _ => panic!("expected a Ball"),
+        }
+        assert!(Shape::Ball { radius: 1.0 }
+            .scaled(&Vector3::new(0.0, 1.0, 1.0))
+            .is_err());
+    }
+
+    #[test]
+    fn add_collider_list() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        // create an Entity with a body capsule and head and feet sensors
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliders::from(vec![
+                PhysicsColliderBuilder::<f32>::from(Shape::Capsule {
+                    half_height: 1.0,
+                    radius: 0.5,
+                })
+                .build(),
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.2 })
+                    .offset_from_parent(Isometry3::translation(0.0, 1.5, 0.0))
+                    .sensor(true)
+                    .build(),
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.2 })
+                    .offset_from_parent(Isometry3::translation(0.0, -1.5, 0.0))
+                    .sensor(true)
+                    .build(),
+            ]))
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        {
+            let physics = world.read_resource::<Physics<f32>>();
+            assert_eq!(physics.collider_list_handles[&entity.id()].len(), 3);
+            assert_eq!(
+                world
+                    .read_resource::<DefaultColliderSet<f32>>()
+                    .iter()
+                    .count(),
+                3
+            );
+        }
+
+        // removing the Component removes all of its colliders
+        world
+            .write_storage::<PhysicsColliders<f32>>()
+            .remove(entity);
+        dispatcher.dispatch(&mut world.res);
+
+        let physics = world.read_resource::<Physics<f32>>();
+        assert!(physics.collider_list_handles.is_empty());
+        assert_eq!(
+            world
+                .read_resource::<DefaultColliderSet<f32>>()
+                .iter()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn attach_collider_to_ancestor() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                HierarchySystem::<PhysicsParent>::new(),
+                "physics_hierarchy_system",
+                &[],
+            )
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncCollidersToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_colliders_to_physics_system",
+                &["sync_bodies_to_physics_system", "physics_hierarchy_system"],
+            )
+            .build();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+
+        let grandparent = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        let other = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        let parent = world
+            .create_entity()
+            .with(PhysicsParent {
+                entity: grandparent,
+            })
+            .build();
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .with(PhysicsParent { entity: parent })
+            .build();
+        dispatcher.dispatch(&mut world.res);
+
+        let body_of = |world: &World| {
+            let handle = world
+                .read_storage::<PhysicsCollider<f32>>()
+                .get(entity)
+                .unwrap()
+                .handle
+                .unwrap();
+            world
+                .read_resource::<DefaultColliderSet<f32>>()
+                .get(handle)
+                .unwrap()
+                .body()
+        };
+        let body_handle =
+            |world: &World, entity| world.read_resource::<Physics<f32>>().body_handles[&entity];
+        assert_eq!(body_of(&world), body_handle(&world, grandparent.id()));
+
+        // re-parent the intermediate Entity and make sure the collider moves along
+        world
+            .write_storage::<PhysicsParent>()
+            .insert(parent, PhysicsParent { entity: other })
+            .unwrap();
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(body_of(&world), body_handle(&world, other.id()));
+
+        // the collider was replaced rather than duplicated
+        let physics = world.read_resource::<Physics<f32>>();
+        assert_eq!(
+            physics.collider_handles.get(&entity.id()).cloned(),
+            world
+                .read_storage::<PhysicsCollider<f32>>()
+                .get(entity)
+                .unwrap()
+                .handle
+        );
+        assert_eq!(
+            world
+                .read_resource::<DefaultColliderSet<f32>>()
+                .iter()
+                .count(),
+            1
+        );
     }
 }
@@ -0,0 +1,227 @@
+use std::{collections::{HashMap, HashSet},
+          marker::PhantomData};
+
+use specs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, ReaderId, Resources, System,
+            SystemData, WriteExpect};
+
+use crate::{bodies::{PhysicsBody, PhysicsTimeScale},
+            events::{ProximityEvent, ProximityEvents},
+            fluids::{FluidGenerator, FluidVolume, SubmergedCollider},
+            Physics};
+use nalgebra::RealField;
+use ncollide::query::Proximity;
+use nphysics::force_generator::{DefaultForceGeneratorHandle, DefaultForceGeneratorSet};
+use nphysics::object::{Collider, DefaultBodyHandle, DefaultColliderSet};
+
+use super::force_generator;
+
+/// A collider of an `Entity`, optionally the `PhysicsColliders` entry `part`.
+type ColliderKey = (Entity, Option<usize>);
+
+/// The `SyncFluidVolumesToPhysicsSystem` tracks which colliders overlap a
+/// `FluidVolume` through the `ProximityEvents` and hands their submerged
+/// volume to the force generator applying buoyancy and drag during the
+/// nphysics step.
+pub struct SyncFluidVolumesToPhysicsSystem<N: RealField> {
+    proximity_events_reader_id: Option<ReaderId<ProximityEvent>>,
+    generator: Option<DefaultForceGeneratorHandle>,
+    /// Pairs of fluid volume and overlapping collider.
+    overlaps: HashSet<(ColliderKey, ColliderKey)>,
+    _phantom: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for SyncFluidVolumesToPhysicsSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, FluidVolume<N>>,
+        ReadStorage<'s, PhysicsBody<N>>,
+        ReadStorage<'s, PhysicsTimeScale<N>>,
+        Read<'s, ProximityEvents>,
+        Read<'s, Physics<N>>,
+        ReadExpect<'s, DefaultColliderSet<N>>,
+        WriteExpect<'s, DefaultForceGeneratorSet<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            fluid_volumes,
+            physics_bodies,
+            time_scales,
+            proximity_events,
+            physics,
+            colliders,
+            mut forces,
+        ) = data;
+
+        // keep track of the colliders overlapping a FluidVolume
+        for event in proximity_events.read(self.proximity_events_reader_id.as_mut().unwrap()) {
+            let collider1 = (event.collider1, event.part1);
+            let collider2 = (event.collider2, event.part2);
+            let overlap = if fluid_volumes.contains(event.collider1) {
+                (collider1, collider2)
+            } else if fluid_volumes.contains(event.collider2) {
+                (collider2, collider1)
+            } else {
+                continue;
+            };
+
+            match event.new_status {
+                Proximity::Intersecting => {
+                    self.overlaps.insert(overlap);
+                }
+                _ => {
+                    self.overlaps.remove(&overlap);
+                }
+            }
+        }
+
+        // forget overlaps of removed entities or FluidVolumes
+        self.overlaps.retain(|((volume, _), (other, _))| {
+            entities.is_alive(*volume)
+                && entities.is_alive(*other)
+                && fluid_volumes.contains(*volume)
+        });
+
+        let generator: &mut FluidGenerator<N> = force_generator(&mut forces, &mut self.generator);
+        let gravity = *physics.gravity();
+
+        // the forces on time scaled bodies are scaled, see FluidGenerator
+        let body_time_scales: HashMap<DefaultBodyHandle, N> = (&physics_bodies, &time_scales)
+            .join()
+            .filter_map(|(physics_body, time_scale)| {
+                physics_body
+                    .handle
+                    .map(|handle| (handle, PhysicsTimeScale::of(Some(time_scale))))
+            })
+            .collect();
+
+        generator.submerged.clear();
+        for (volume, other) in &self.overlaps {
+            let (fluid_collider, submerged_collider) = match (
+                collider(volume, &physics, &colliders),
+                collider(other, &physics, &colliders),
+            ) {
+                (Some(fluid_collider), Some(submerged_collider)) => {
+                    (fluid_collider, submerged_collider)
+                }
+                _ => continue,
+            };
+
+            let body = submerged_collider.body();
+            generator.submerged.extend(SubmergedCollider::new(
+                body,
+                &submerged_collider
+                    .shape()
+                    .aabb(submerged_collider.position()),
+                &fluid_collider.shape().aabb(fluid_collider.position()),
+                fluid_volumes.get(volume.0).unwrap(),
+                &gravity,
+                body_time_scales.get(&body).cloned().unwrap_or_else(N::one),
+            ));
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        // register reader id for the ProximityEvents
+        self.proximity_events_reader_id =
+            Some(res.fetch_mut::<ProximityEvents>().register_reader());
+    }
+}
+
+fn collider<'a, N: RealField>(
+    (entity, part): &ColliderKey,
+    physics: &Physics<N>,
+    colliders: &'a DefaultColliderSet<N>,
+) -> Option<&'a Collider<N, DefaultBodyHandle>> {
+    physics
+        .collider_handle(entity.id(), *part)
+        .and_then(|handle| colliders.get(handle))
+}
+
+impl<N: RealField> Default for SyncFluidVolumesToPhysicsSystem<N> {
+    fn default() -> Self {
+        Self {
+            proximity_events_reader_id: None,
+            generator: None,
+            overlaps: HashSet::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use specs::{world::Builder, World};
+
+    use crate::{bodies::PhysicsTimeScale, colliders::Shape, fluids::FluidVolume,
+                parameters::Gravity, physics_dispatcher, PhysicsBody, PhysicsBodyBuilder,
+                PhysicsColliderBuilder, SimplePosition};
+    use nalgebra::{Isometry3, Vector3};
+    use nphysics::{force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
+
+    /// Drops a ball with the given time scale into a pool and returns its
+    /// vertical velocity.
+    fn drop_ball(fluid: bool, time_scale: f32) -> f32 {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+        world.add_resource(Gravity(Vector3::<f32>::new(0.0, -10.0, 0.0)));
+
+        let mut pool = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 10.0, 10.0),
+                })
+                .sensor(true)
+                .build(),
+            );
+        if fluid {
+            pool = pool.with(FluidVolume::<f32> {
+                density: 10.0,
+                ..FluidVolume::default()
+            });
+        }
+        pool.build();
+
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .with(PhysicsTimeScale(time_scale))
+            .build();
+
+        for _ in 0..10 {
+            dispatcher.dispatch(&mut world.res);
+        }
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        physics_bodies.get(ball).unwrap().velocity.linear.y
+    }
+
+    #[test]
+    fn buoyancy() {
+        assert!(drop_ball(false, 1.0) < 0.0);
+        assert!(drop_ball(true, 1.0) > 0.0);
+
+        // a slowed down ball rises slower
+        let slowed = drop_ball(true, 0.5);
+        assert!(slowed > 0.0 && slowed < drop_ball(true, 1.0));
+    }
+}
@@ -0,0 +1,205 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use specs::{storage::ComponentEvent, world::Index, Entities, Join, Read, ReadStorage, ReaderId,
+            Resources, System, SystemData, WriteExpect, WriteStorage};
+
+use crate::{bodies::{PhysicsBody, PhysicsTimeScale},
+            colliders::ShapeCache,
+            gravity::{ActiveGravityField, GravityField, GravityFieldGenerator},
+            pose::Pose,
+            Physics};
+use nalgebra::RealField;
+use ncollide::shape::ShapeHandle;
+use nphysics::force_generator::{DefaultForceGeneratorHandle, DefaultForceGeneratorSet};
+
+use super::{force_generator, iterate_component_events};
+
+/// The `SyncGravityFieldsToPhysicsSystem` handles the synchronisation of
+/// `GravityField` `Component`s into the force generator applying them during
+/// the nphysics step.
+pub struct SyncGravityFieldsToPhysicsSystem<N: RealField, P> {
+    gravity_fields_reader_id: Option<ReaderId<ComponentEvent>>,
+    generator: Option<DefaultForceGeneratorHandle>,
+    shapes: HashMap<Index, ShapeHandle<N>>,
+    _phantom: PhantomData<P>,
+}
+
+impl<'s, N, P> System<'s> for SyncGravityFieldsToPhysicsSystem<N, P>
+where
+    N: RealField,
+    P: Pose<N>,
+{
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, P>,
+        ReadStorage<'s, GravityField<N>>,
+        ReadStorage<'s, PhysicsBody<N>>,
+        ReadStorage<'s, PhysicsTimeScale<N>>,
+        Read<'s, ShapeCache<N>>,
+        Read<'s, Physics<N>>,
+        WriteExpect<'s, DefaultForceGeneratorSet<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            positions,
+            gravity_fields,
+            physics_bodies,
+            time_scales,
+            shape_cache,
+            physics,
+            mut forces,
+        ) = data;
+
+        // collect all ComponentEvents for the GravityField storage
+        let (inserted_gravity_fields, modified_gravity_fields, removed_gravity_fields) =
+            iterate_component_events(
+                &gravity_fields,
+                self.gravity_fields_reader_id.as_mut().unwrap(),
+            );
+
+        // build the shapes of inserted or modified zones once, instead of every step
+        for id in (&removed_gravity_fields).join() {
+            self.shapes.remove(&id);
+        }
+        for (gravity_field, id) in (
+            &gravity_fields,
+            &inserted_gravity_fields | &modified_gravity_fields,
+        )
+            .join()
+        {
+            self.shapes.remove(&id);
+            if let GravityField::Zone { shape, .. } = gravity_field {
+                match shape.cached_handle(&shape_cache) {
+                    Ok(handle) => {
+                        self.shapes.insert(id, handle);
+                    }
+                    Err(err) => error!("Invalid shape of GravityField with id {}: {}", id, err),
+                }
+            }
+        }
+
+        // insert the force generator on the first run or if it was removed
+        let generator: &mut GravityFieldGenerator<N> =
+            force_generator(&mut forces, &mut self.generator);
+
+        generator.gravity = *physics.gravity();
+
+        generator.fields.clear();
+        for (entity, position, gravity_field) in (&entities, &positions, &gravity_fields).join() {
+            let shape = self.shapes.get(&entity.id());
+            generator.fields.extend(ActiveGravityField::new(
+                gravity_field,
+                position.isometry(),
+                shape,
+            ));
+        }
+
+        // accelerations of time scaled bodies are scaled quadratically, just like
+        // the global gravity
+        generator.bodies.clear();
+        for (physics_body, time_scale) in (&physics_bodies, time_scales.maybe()).join() {
+            if !physics_body.gravity_fields_enabled {
+                continue;
+            }
+
+            if let Some(handle) = physics_body.handle {
+                let time_scale = PhysicsTimeScale::of(time_scale);
+                generator.bodies.push((handle, time_scale * time_scale));
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        // register reader id for the GravityField storage
+        let mut gravity_field_storage: WriteStorage<GravityField<N>> = SystemData::fetch(&res);
+        self.gravity_fields_reader_id = Some(gravity_field_storage.register_reader());
+    }
+}
+
+impl<N: RealField, P> Default for SyncGravityFieldsToPhysicsSystem<N, P> {
+    fn default() -> Self {
+        Self {
+            gravity_fields_reader_id: None,
+            generator: None,
+            shapes: HashMap::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use specs::{world::Builder, Entity, World};
+
+    use crate::{colliders::Shape, gravity::GravityField, parameters::Gravity, physics_dispatcher,
+                PhysicsBody, PhysicsBodyBuilder, SimplePosition};
+    use nalgebra::{Isometry3, Vector3};
+    use nphysics::{force_generator::DefaultForceGeneratorSet,
+                   joint::DefaultJointConstraintSet,
+                   object::{BodyStatus, DefaultBodySet, DefaultColliderSet}};
+
+    fn create_body(world: &mut World, x: f32, gravity_fields_enabled: bool) -> Entity {
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .gravity_fields_enabled(gravity_fields_enabled)
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn gravity_fields() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        world.add_resource(DefaultBodySet::<f32>::new());
+        world.add_resource(DefaultColliderSet::<f32>::new());
+        world.add_resource(DefaultJointConstraintSet::<f32>::new());
+        world.add_resource(DefaultForceGeneratorSet::<f32>::new());
+        dispatcher.setup(&mut world.res);
+        world.add_resource(Gravity(Vector3::<f32>::new(0.0, -6.0, 0.0)));
+
+        // a constant attractor at the origin and a zero-g zone around x = 100
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(GravityField::<f32>::Attractor {
+                strength: 6.0,
+                falloff: 0.0,
+                radius: 10.0,
+            })
+            .build();
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                100.0, 0.0, 0.0,
+            )))
+            .with(GravityField::<f32>::Zone {
+                acceleration: Vector3::zeros(),
+                shape: Shape::Cuboid {
+                    half_extents: Vector3::new(5.0, 5.0, 5.0),
+                },
+            })
+            .build();
+
+        let attracted = create_body(&mut world, 5.0, true);
+        let opted_out = create_body(&mut world, -5.0, false);
+        let weightless = create_body(&mut world, 100.0, true);
+        let falling = create_body(&mut world, 200.0, true);
+        dispatcher.dispatch(&mut world.res);
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = |entity| physics_bodies.get(entity).unwrap().velocity.linear;
+        assert!((velocity(attracted).x + 0.1).abs() < 1.0e-4);
+        assert_eq!(velocity(opted_out).x, 0.0);
+        assert!(velocity(weightless).norm() < 1.0e-4);
+        assert!((velocity(falling).y + 0.1).abs() < 1.0e-4);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{marker::PhantomData,
+          sync::{Arc, Mutex}};
+
+use specs::{Resources, System, SystemData};
+
+use crate::{filters::{lock, PairFilter},
+            Physics, PhysicsWorld};
+use nalgebra::RealField;
+
+/// The `SyncPairFilterToPhysicsSystem` registers a `PairFilter` with the broad
+/// phase of the nphysics `World` and updates it from its `SystemData` every
+/// frame. It has to run before the `PhysicsStepperSystem`; when registering
+/// the physics `System`s through `register_physics_systems`, add it to the
+/// `DispatcherBuilder` first.
+pub struct SyncPairFilterToPhysicsSystem<N, F> {
+    filter: Arc<Mutex<F>>,
+    _phantom: PhantomData<N>,
+}
+
+impl<N, F> SyncPairFilterToPhysicsSystem<N, F> {
+    /// Creates a new `SyncPairFilterToPhysicsSystem` applying the given
+    /// `PairFilter`.
+    pub fn new(filter: F) -> Self {
+        Self {
+            filter: Arc::new(Mutex::new(filter)),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'s, N, F> System<'s> for SyncPairFilterToPhysicsSystem<N, F>
+where
+    N: RealField,
+    F: for<'a> PairFilter<'a>,
+{
+    type SystemData = (<F as PairFilter<'s>>::SystemData, PhysicsWorld<'s, N>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (filter_data, physics) = data;
+
+        // pairs which already interact are revisited by the broad phase if the
+        // decisions of the filter changed
+        if lock(&self.filter).update(filter_data) {
+            debug!("Pair filter changed, revisiting all interacting pairs.");
+            lock(&physics.pair_filters).set_modified();
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        info!("SyncPairFilterToPhysicsSystem.setup");
+        Self::SystemData::setup(res);
+
+        let physics = res.fetch_mut::<Physics<N>>();
+        lock(&physics.pair_filters).add(self.filter.clone());
+    }
+}
+
+impl<N, F: Default> Default for SyncPairFilterToPhysicsSystem<N, F> {
+    fn default() -> Self {
+        Self::new(F::default())
+    }
+}
+
+#[cfg(all(test, feature = "physics3d"))]
+mod tests {
+    use specs::world::Builder;
+
+    use crate::{colliders::Shape, events::ProximityEvents, filters::IgnoreCollisionsWith,
+                parameters::Gravity, test_world, PhysicsBodyBuilder, PhysicsColliderBuilder,
+                SimplePosition};
+    use nalgebra::{Isometry3, Vector3};
+    use nphysics::object::BodyStatus;
+
+    /// Drops a ball onto the ground for a second and returns its height; after
+    /// the given amount of frames it starts to ignore collisions with the
+    /// ground.
+    fn falling_ball(ignore_ground_after: Option<usize>) -> f32 {
+        let (mut world, mut dispatcher) = test_world::<SimplePosition<f32>>();
+        world.add_resource(Gravity::<f32>(Vector3::new(0.0, -9.81, 0.0)));
+
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(5.0, 0.1, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 0.7, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+
+        for frame in 0..60 {
+            if ignore_ground_after == Some(frame) {
+                world
+                    .write_storage::<IgnoreCollisionsWith>()
+                    .insert(ball, IgnoreCollisionsWith(vec![ground]))
+                    .unwrap();
+            }
+            dispatcher.dispatch(&mut world.res);
+        }
+
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        positions.get(ball).unwrap().0.translation.vector.y
+    }
+
+    #[test]
+    fn ignore_collisions_with() {
+        assert!(falling_ball(None) > 0.0);
+        assert!(falling_ball(Some(0)) < 0.0);
+        // the ball already resting on the ground falls through it as well
+        assert!(falling_ball(Some(30)) < 0.0);
+    }
+
+    #[test]
+    fn ignore_sensor() {
+        let (mut world, mut dispatcher) = test_world::<SimplePosition<f32>>();
+        let mut reader = world.write_resource::<ProximityEvents>().register_reader();
+
+        let sensor = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 })
+                    .sensor(true)
+                    .build(),
+            )
+            .build();
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .with(IgnoreCollisionsWith(vec![sensor]))
+            .build();
+
+        for _ in 0..5 {
+            dispatcher.dispatch(&mut world.res);
+        }
+        let events = world.read_resource::<ProximityEvents>();
+        assert_eq!(events.read(&mut reader).count(), 0);
+    }
+}
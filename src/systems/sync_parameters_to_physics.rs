@@ -1,20 +1,24 @@
-use std::marker::PhantomData;
-
-use specs::{Read, System};
+use specs::{Read, Resources, System, SystemData, Write};
 
 use crate::parameters::{Gravity, PhysicsIntegrationParameters, PhysicsProfilingEnabled};
-use crate::PhysicsWorld;
+use crate::{Physics, PhysicsWorld};
 use nalgebra::RealField;
 
 /// The `SyncParametersToPhysicsSystem` synchronises the simulation parameters
 /// with the nphysics `World`.
-pub struct SyncParametersToPhysicsSystem<N>(PhantomData<N>);
+///
+/// Modified `PhysicsIntegrationParameters` are validated before they are
+/// applied; invalid ones are logged and replaced by the values currently used
+/// by the nphysics `World`.
+pub struct SyncParametersToPhysicsSystem<N: RealField> {
+    synced: Option<PhysicsIntegrationParameters<N>>,
+}
 
 impl<'s, N: RealField> System<'s> for SyncParametersToPhysicsSystem<N> {
     type SystemData = (
         Option<Read<'s, Gravity<N>>>,
         Option<Read<'s, PhysicsProfilingEnabled>>,
-        Option<Read<'s, PhysicsIntegrationParameters<N>>>,
+        Option<Write<'s, PhysicsIntegrationParameters<N>>>,
         PhysicsWorld<'s, N>,
     );
 
@@ -43,18 +47,42 @@ impl<'s, N: RealField> System<'s> for SyncParametersToPhysicsSystem<N> {
             }
         }
 
-        if let Some(params) = integration_params {
-            if *params != *physics.integration_parameters() {
-                params.apply(&mut physics.mechanical_world.integration_parameters);
-                info!("Integration parameters have been updated.");
+        if let Some(mut params) = integration_params {
+            if self.synced.as_ref() != Some(&*params) {
+                match params.validate() {
+                    Ok(()) => {
+                        params.apply(
+                            self.synced.as_ref(),
+                            &mut physics.mechanical_world.integration_parameters,
+                        );
+                        info!("Integration parameters have been updated.");
+                    }
+                    Err(err) => error!("Invalid integration parameters: {}", err),
+                }
             }
+
+            // read back the values used by the nphysics World, which also reverts
+            // rejected modifications and keeps the elapsed time up to date
+            *params = PhysicsIntegrationParameters::from(physics.integration_parameters());
+            self.synced = Some(*params);
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        if !res.has_value::<PhysicsIntegrationParameters<N>>() {
+            let params = PhysicsIntegrationParameters::from(
+                res.fetch::<Physics<N>>().integration_parameters(),
+            );
+            res.insert(params);
         }
     }
 }
 
-impl<N> Default for SyncParametersToPhysicsSystem<N> {
+impl<N: RealField> Default for SyncParametersToPhysicsSystem<N> {
     fn default() -> Self {
-        Self(PhantomData)
+        Self { synced: None }
     }
 }
 
@@ -62,7 +90,9 @@ impl<N> Default for SyncParametersToPhysicsSystem<N> {
 mod tests {
     use specs::{DispatcherBuilder, World};
 
-    use crate::{parameters::Gravity, systems::SyncParametersToPhysicsSystem, Physics};
+    use crate::{parameters::{Gravity, PhysicsIntegrationParameters},
+                systems::SyncParametersToPhysicsSystem,
+                Physics};
     use nalgebra::Vector3;
 
     #[test]
@@ -81,8 +111,112 @@ mod tests {
         dispatcher.dispatch(&mut world.res);
 
         let physics = world.read_resource::<Physics<f32>>();
-        assert_eq!(physics.world.gravity().x, 1.0);
-        assert_eq!(physics.world.gravity().y, 2.0);
-        assert_eq!(physics.world.gravity().z, 3.0);
+        assert_eq!(physics.gravity().x, 1.0);
+        assert_eq!(physics.gravity().y, 2.0);
+        assert_eq!(physics.gravity().z, 3.0);
+    }
+
+    #[test]
+    fn integration_parameters() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncParametersToPhysicsSystem::<f32>::default(),
+                "sync_parameters_to_physics_system",
+                &[],
+            )
+            .build();
+        dispatcher.setup(&mut world.res);
+
+        // the resource is populated from the nphysics World
+        assert!(
+            *world.read_resource::<PhysicsIntegrationParameters<f32>>()
+                == *world
+                    .read_resource::<Physics<f32>>()
+                    .integration_parameters()
+        );
+
+        world
+            .write_resource::<PhysicsIntegrationParameters<f32>>()
+            .error_reduction_parameter = 0.5;
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .erp,
+            0.5
+        );
+
+        // out of range values are rejected and reverted
+        world
+            .write_resource::<PhysicsIntegrationParameters<f32>>()
+            .error_reduction_parameter = 2.0;
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .erp,
+            0.5
+        );
+        assert_eq!(
+            world
+                .read_resource::<PhysicsIntegrationParameters<f32>>()
+                .error_reduction_parameter,
+            0.5
+        );
+    }
+
+    #[test]
+    fn keep_elapsed_time() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncParametersToPhysicsSystem::<f32>::default(),
+                "sync_parameters_to_physics_system",
+                &[],
+            )
+            .build();
+        dispatcher.setup(&mut world.res);
+        dispatcher.dispatch(&mut world.res);
+
+        // steps advance the elapsed time after the parameters were synced, which
+        // modifying another parameter mustn't rewind
+        world
+            .write_resource::<Physics<f32>>()
+            .mechanical_world
+            .integration_parameters
+            .t = 5.0;
+        world
+            .write_resource::<PhysicsIntegrationParameters<f32>>()
+            .error_reduction_parameter = 0.5;
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .t,
+            5.0
+        );
+        assert_eq!(
+            world
+                .read_resource::<PhysicsIntegrationParameters<f32>>()
+                .elapsed_time,
+            5.0
+        );
+
+        // setting it explicitly still applies
+        world
+            .write_resource::<PhysicsIntegrationParameters<f32>>()
+            .elapsed_time = 1.0;
+        dispatcher.dispatch(&mut world.res);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .t,
+            1.0
+        );
     }
 }
@@ -0,0 +1,496 @@
+//! # Tilemap module
+//! Generation of colliders for 2D tilemaps. Adjacent tiles are merged, so
+//! bodies sliding across a floor made of many tiles don't snag on the edges
+//! between them.
+//!
+//! The map is split into square chunks; changing a tile only regenerates the
+//! colliders of the chunks touching it.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use specs_physics::{nalgebra::{Isometry2, Vector2},
+//!                     tilemap::TileMapBuilder,
+//!                     PhysicsColliderBuilder, SimplePosition};
+//!
+//! let mut tile_map = TileMapBuilder::<f32>::from_solid(64, 32, &solid)
+//!     .tile_size(Vector2::new(16.0, 16.0))
+//!     .chunk_size(16)
+//!     .build()?;
+//!
+//! tile_map.sync_entities(&mut world, |region| {
+//!     (
+//!         SimplePosition(Isometry2::identity()),
+//!         PhysicsColliderBuilder::from(region.shape.clone()).build(),
+//!     )
+//! });
+//!
+//! // dig a hole, only the chunk containing the tile is regenerated
+//! tile_map.set_tile(3, 4, 0);
+//! tile_map.sync_entities(&mut world, ...);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use nalgebra::{convert, Point2, RealField, Vector2};
+use nphysics::{math::Isometry, object::BodyStatus};
+use specs::{world::Builder, Entity, World};
+
+use crate::{bodies::PhysicsBodyBuilder,
+            colliders::{PhysicsCollider, Shape},
+            error::PhysicsError,
+            pose::Pose};
+
+/// The id of empty tiles, all other ids are solid.
+pub const EMPTY_TILE: u32 = 0;
+
+/// Determines which `Shape`s are generated for the tiles of a `TileMap`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TileColliderKind {
+    /// A `Shape::Polyline` following the outline of adjacent tiles. Edges
+    /// between solid tiles are left out, so nothing snags on them.
+    Outline,
+    /// A `Shape::Compound` of cuboids, each covering as many adjacent tiles as
+    /// possible. Unlike outlines these are solid, but bodies may snag on the
+    /// edges between them.
+    Rectangles,
+}
+
+/// The `Shape` generated for all tiles with the same id within a chunk of a
+/// `TileMap`.
+#[derive(Clone)]
+pub struct TileRegion<N: RealField> {
+    /// The column and row of the chunk.
+    pub chunk: (usize, usize),
+    /// The id shared by all tiles of the region.
+    pub id: u32,
+    /// The `Shape` covering the tiles, relative to the origin of the map.
+    pub shape: Shape<N>,
+}
+
+/// The `TileMapBuilder` implements the builder pattern for `TileMap`s.
+pub struct TileMapBuilder<N: RealField> {
+    width: usize,
+    height: usize,
+    tiles: Vec<u32>,
+    tile_size: Vector2<N>,
+    chunk_size: usize,
+    kind: TileColliderKind,
+}
+
+impl<N: RealField> TileMapBuilder<N> {
+    /// Creates a new `TileMapBuilder` from the ids of `width` x `height` tiles,
+    /// stored row by row starting at the bottom left. Tiles with the id
+    /// `EMPTY_TILE` are empty, adjacent tiles are only merged if they share
+    /// the same id.
+    pub fn new(width: usize, height: usize, tiles: Vec<u32>) -> Self {
+        Self {
+            width,
+            height,
+            tiles,
+            tile_size: Vector2::repeat(N::one()),
+            chunk_size: 32,
+            kind: TileColliderKind::Outline,
+        }
+    }
+
+    /// Creates a new `TileMapBuilder` from tiles that are either solid or
+    /// empty, stored row by row starting at the bottom left.
+    pub fn from_solid(width: usize, height: usize, solid: &[bool]) -> Self {
+        Self::new(width, height, solid.iter().map(|x| u32::from(*x)).collect())
+    }
+
+    /// Sets the `tile_size` value of the `TileMapBuilder`.
+    pub fn tile_size(mut self, tile_size: Vector2<N>) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Sets the `chunk_size` value of the `TileMapBuilder`, which is the
+    /// amount of tiles along each side of a chunk.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the `kind` value of the `TileMapBuilder`.
+    pub fn kind(mut self, kind: TileColliderKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Builds the `TileMap` from the values set in the `TileMapBuilder`
+    /// instance. Fails if the amount of tiles doesn't match the dimensions.
+    pub fn build(self) -> Result<TileMap<N>, PhysicsError> {
+        if self.tiles.len() != self.width * self.height {
+            return Err(PhysicsError::InvalidSize {
+                shape: "TileMap",
+                expected: format!("{}x{} tiles", self.width, self.height),
+                actual: format!("{} tiles", self.tiles.len()),
+            });
+        }
+        if self.chunk_size == 0 {
+            return Err(PhysicsError::InvalidDimension {
+                shape: "TileMap",
+                dimension: "chunk_size",
+            });
+        }
+
+        let mut tile_map = TileMap {
+            width: self.width,
+            height: self.height,
+            tiles: self.tiles,
+            tile_size: self.tile_size,
+            chunk_size: self.chunk_size,
+            kind: self.kind,
+            modified_chunks: HashSet::new(),
+            entities: HashMap::new(),
+        };
+        tile_map.modified_chunks = tile_map.chunks().collect();
+
+        Ok(tile_map)
+    }
+}
+
+/// A `TileMap` generates merged colliders for a grid of tiles and keeps track
+/// of the chunks whose tiles changed since their colliders were last synced.
+pub struct TileMap<N: RealField> {
+    width: usize,
+    height: usize,
+    tiles: Vec<u32>,
+    tile_size: Vector2<N>,
+    chunk_size: usize,
+    kind: TileColliderKind,
+    modified_chunks: HashSet<(usize, usize)>,
+    entities: HashMap<(usize, usize, u32), Entity>,
+}
+
+impl<N: RealField> TileMap<N> {
+    /// Returns the id of the tile at the given column and row, or `EMPTY_TILE`
+    /// if it lies outside of the map.
+    pub fn tile(&self, x: usize, y: usize) -> u32 {
+        match x < self.width && y < self.height {
+            true => self.tiles[y * self.width + x],
+            false => EMPTY_TILE,
+        }
+    }
+
+    /// Changes the id of the tile at the given column and row. The chunks
+    /// touching the tile are regenerated on the next sync.
+    pub fn set_tile(&mut self, x: usize, y: usize, id: u32) {
+        if x >= self.width || y >= self.height || self.tile(x, y) == id {
+            return;
+        }
+        self.tiles[y * self.width + x] = id;
+
+        // the outlines of neighbouring chunks depend on the tiles along their border
+        let neighbours = [
+            (x, y),
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (x, y) in neighbours.iter() {
+            if *x < self.width && *y < self.height {
+                self.modified_chunks
+                    .insert((x / self.chunk_size, y / self.chunk_size));
+            }
+        }
+    }
+
+    /// Returns the `TileRegion`s of all chunks.
+    pub fn regions(&self) -> Vec<TileRegion<N>> {
+        self.chunks()
+            .flat_map(|chunk| self.chunk_regions(chunk))
+            .collect()
+    }
+
+    /// Returns the `TileRegion`s of the chunk at the given chunk column and
+    /// row, one for each id of its tiles.
+    pub fn chunk_regions(&self, chunk: (usize, usize)) -> Vec<TileRegion<N>> {
+        let mut ids = self
+            .chunk_tiles(chunk)
+            .map(|(x, y)| self.tile(x, y))
+            .filter(|id| *id != EMPTY_TILE)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| TileRegion {
+                chunk,
+                id,
+                shape: match self.kind {
+                    TileColliderKind::Outline => self.outline(chunk, id),
+                    TileColliderKind::Rectangles => self.rectangles(chunk, id),
+                },
+            })
+            .collect()
+    }
+
+    /// Creates, updates and deletes one static `Entity` per `TileRegion` of the
+    /// chunks modified since the last call. `create` returns the `Pose` and
+    /// `PhysicsCollider` of new entities, which should use the `shape` of the
+    /// given `TileRegion`; existing entities only get their shape replaced.
+    pub fn sync_entities<P, F>(&mut self, world: &mut World, mut create: F)
+    where
+        P: Pose<N>,
+        F: FnMut(&TileRegion<N>) -> (P, PhysicsCollider<N>),
+    {
+        let mut modified_chunks = self.modified_chunks.drain().collect::<Vec<_>>();
+        modified_chunks.sort();
+
+        for chunk in modified_chunks {
+            let regions = self.chunk_regions(chunk);
+
+            // delete the entities of ids no longer present in the chunk
+            let outdated = self
+                .entities
+                .keys()
+                .filter(|key| (key.0, key.1) == chunk)
+                .filter(|key| regions.iter().all(|region| region.id != key.2))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in outdated {
+                if let Some(entity) = self.entities.remove(&key) {
+                    if let Err(err) = world.delete_entity(entity) {
+                        warn!("Failed to delete tile map entity: {:?}", err);
+                    }
+                }
+            }
+
+            for region in regions {
+                let key = (chunk.0, chunk.1, region.id);
+                let existing = self
+                    .entities
+                    .get(&key)
+                    .filter(|entity| world.is_alive(**entity))
+                    .cloned();
+
+                match existing {
+                    Some(entity) => {
+                        let mut physics_colliders = world.write_storage::<PhysicsCollider<N>>();
+                        if let Some(physics_collider) = physics_colliders.get_mut(entity) {
+                            physics_collider.shape = region.shape;
+                        }
+                    }
+                    None => {
+                        let (pose, physics_collider) = create(&region);
+                        let entity = world
+                            .create_entity()
+                            .with(pose)
+                            .with(PhysicsBodyBuilder::<N>::from(BodyStatus::Static).build())
+                            .with(physics_collider)
+                            .build();
+                        self.entities.insert(key, entity);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the `Entity` created by `TileMap::sync_entities` for the tiles
+    /// with the given id within a chunk.
+    pub fn entity(&self, chunk: (usize, usize), id: u32) -> Option<Entity> {
+        self.entities.get(&(chunk.0, chunk.1, id)).cloned()
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = (usize, usize)> {
+        let columns = (self.width + self.chunk_size - 1) / self.chunk_size;
+        let rows = (self.height + self.chunk_size - 1) / self.chunk_size;
+        (0..rows).flat_map(move |y| (0..columns).map(move |x| (x, y)))
+    }
+
+    fn chunk_tiles(&self, chunk: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        let x_range = chunk.0 * self.chunk_size..((chunk.0 + 1) * self.chunk_size).min(self.width);
+        let y_range = chunk.1 * self.chunk_size..((chunk.1 + 1) * self.chunk_size).min(self.height);
+        y_range.flat_map(move |y| x_range.clone().map(move |x| (x, y)))
+    }
+
+    /// Returns `true` if the tile is part of the region, tiles next to the map
+    /// count as empty.
+    fn is(&self, x: isize, y: isize, id: u32) -> bool {
+        x >= 0 && y >= 0 && self.tile(x as usize, y as usize) == id
+    }
+
+    fn corner(&self, x: usize, y: usize) -> Point2<N> {
+        Point2::new(
+            self.tile_size.x * convert(x as f64),
+            self.tile_size.y * convert(y as f64),
+        )
+    }
+
+    /// Collects the edges between the tiles of the region and other tiles. As
+    /// neighbours in other chunks are taken into account, edges along the
+    /// chunk borders are left out as well. Collinear edges are merged.
+    fn outline(&self, chunk: (usize, usize), id: u32) -> Shape<N> {
+        let tiles = self
+            .chunk_tiles(chunk)
+            .filter(|(x, y)| self.tile(*x, *y) == id)
+            .collect::<HashSet<_>>();
+
+        // edges are keyed by their start corner along with the side they face;
+        // running along a row or column lets consecutive edges be merged
+        let mut segments = Vec::new();
+        for &(dx, dy) in &[(0isize, -1isize), (0, 1), (-1, 0), (1, 0)] {
+            let horizontal = dx == 0;
+            let mut edges = tiles
+                .iter()
+                .filter(|(x, y)| !self.is(*x as isize + dx, *y as isize + dy, id))
+                .map(|(x, y)| match horizontal {
+                    true => (y + (dy > 0) as usize, *x),
+                    false => (x + (dx > 0) as usize, *y),
+                })
+                .collect::<Vec<_>>();
+            edges.sort();
+
+            let mut edges = edges.into_iter().peekable();
+            while let Some((line, start)) = edges.next() {
+                let mut end = start + 1;
+                while edges.peek() == Some(&(line, end)) {
+                    edges.next();
+                    end += 1;
+                }
+                segments.push(match horizontal {
+                    true => ((start, line), (end, line)),
+                    false => ((line, start), (line, end)),
+                });
+            }
+        }
+
+        // share the corners between the segments
+        let mut points = Vec::new();
+        let mut corners = HashMap::new();
+        let mut indices = Vec::new();
+        for (a, b) in segments {
+            let mut index = |corner: (usize, usize)| {
+                *corners.entry(corner).or_insert_with(|| {
+                    points.push(self.corner(corner.0, corner.1));
+                    points.len() - 1
+                })
+            };
+            let a = index(a);
+            let b = index(b);
+            indices.push(Point2::new(a, b));
+        }
+
+        Shape::Polyline {
+            points,
+            indices: Some(indices),
+        }
+    }
+
+    /// Greedily covers the tiles of the region with as few rectangles as
+    /// possible, growing each one to the right first and upwards second.
+    fn rectangles(&self, chunk: (usize, usize), id: u32) -> Shape<N> {
+        let mut covered = HashSet::new();
+        let mut parts = Vec::new();
+        let x_end = ((chunk.0 + 1) * self.chunk_size).min(self.width);
+        let y_end = ((chunk.1 + 1) * self.chunk_size).min(self.height);
+        let fits = |x: usize, y: usize, covered: &HashSet<(usize, usize)>| {
+            self.tile(x, y) == id && !covered.contains(&(x, y))
+        };
+
+        for (x, y) in self.chunk_tiles(chunk) {
+            if !fits(x, y, &covered) {
+                continue;
+            }
+
+            let mut width = 1;
+            while x + width < x_end && fits(x + width, y, &covered) {
+                width += 1;
+            }
+            let mut height = 1;
+            while y + height < y_end && (x..x + width).all(|x| fits(x, y + height, &covered)) {
+                height += 1;
+            }
+            for covered_y in y..y + height {
+                for covered_x in x..x + width {
+                    covered.insert((covered_x, covered_y));
+                }
+            }
+
+            let min = self.corner(x, y);
+            let max = self.corner(x + width, y + height);
+            let half_extents = (max - min) / convert::<f64, N>(2.0);
+            parts.push((
+                Isometry::translation(min.x + half_extents.x, min.y + half_extents.y),
+                Shape::Cuboid { half_extents },
+            ));
+        }
+
+        Shape::Compound { parts }
+    }
+}
+
+#[cfg(all(test, feature = "physics2d"))]
+mod tests {
+    use super::{TileColliderKind, TileMapBuilder, EMPTY_TILE};
+    use crate::{colliders::Shape, error::PhysicsError};
+
+    #[test]
+    fn merge_outline() {
+        // a floor of four tiles with a single tile on top
+        let tile_map = TileMapBuilder::<f32>::from_solid(
+            4,
+            2,
+            &[true, true, true, true, false, true, false, false],
+        )
+        .build()
+        .unwrap();
+
+        let regions = tile_map.regions();
+        assert_eq!(regions.len(), 1);
+        match &regions[0].shape {
+            Shape::Polyline { indices, .. } => {
+                // bottom, two tops of the floor, top of the tile, four sides
+                assert_eq!(indices.as_ref().unwrap().len(), 8);
+            }
+            _ => panic!("expected a Polyline"),
+        }
+    }
+
+    #[test]
+    fn tile_count_mismatch() {
+        match TileMapBuilder::<f32>::from_solid(3, 2, &[true; 5]).build() {
+            Err(PhysicsError::InvalidSize {
+                expected, actual, ..
+            }) => {
+                assert_eq!(expected, "3x2 tiles");
+                assert_eq!(actual, "5 tiles");
+            }
+            _ => panic!("expected InvalidSize"),
+        }
+    }
+
+    #[test]
+    fn merge_rectangles() {
+        let tile_map = TileMapBuilder::<f32>::from_solid(3, 2, &[true; 6])
+            .kind(TileColliderKind::Rectangles)
+            .build()
+            .unwrap();
+
+        match &tile_map.regions()[0].shape {
+            Shape::Compound { parts } => assert_eq!(parts.len(), 1),
+            _ => panic!("expected a Compound"),
+        }
+    }
+
+    #[test]
+    fn split_chunks() {
+        let mut tile_map = TileMapBuilder::<f32>::from_solid(4, 1, &[true; 4])
+            .chunk_size(2)
+            .build()
+            .unwrap();
+        assert_eq!(tile_map.regions().len(), 2);
+
+        tile_map.modified_chunks.clear();
+        tile_map.set_tile(0, 0, EMPTY_TILE);
+        assert_eq!(tile_map.modified_chunks.len(), 1);
+
+        // tiles along the border also affect the neighbouring chunk
+        tile_map.set_tile(2, 0, EMPTY_TILE);
+        assert_eq!(tile_map.modified_chunks.len(), 2);
+    }
+}